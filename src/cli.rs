@@ -0,0 +1,1417 @@
+use anyhow::{Result, format_err};
+use clap::{Args, Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::instructions::clmm::clmm_close_position::clmm_close_position;
+use crate::instructions::clmm::clmm_collect_remaining_rewards::clmm_collect_remaining_rewards;
+use crate::instructions::clmm::clmm_collect_rewards::clmm_collect_rewards;
+use crate::instructions::clmm::clmm_config::{FEE_RATE_DENOMINATOR_VALUE, fetch_amm_config};
+use crate::instructions::clmm::clmm_create_pool::clmm_create_pool;
+use crate::instructions::clmm::clmm_decrease_liquidity::clmm_decrease_liquidity;
+use crate::instructions::clmm::clmm_increase_liquidity::clmm_increase_liquidity;
+use crate::instructions::clmm::clmm_initialize_reward::clmm_initialize_reward;
+use crate::instructions::clmm::clmm_limit_order::{
+    LimitOrderBook, LimitOrderStatus, clmm_close_limit_order, clmm_place_limit_order,
+    clmm_settle_limit_orders,
+};
+use crate::instructions::clmm::clmm_observation::fetch_observation_state;
+use crate::instructions::clmm::clmm_open_position::clmm_open_position;
+use crate::instructions::clmm::clmm_pool::{ClmmPool, RAYDIUM_CLMM_PROGRAM_ID, fetch_pool_state};
+use crate::instructions::clmm::clmm_position::{ClmmPosition, fetch_personal_position};
+use crate::instructions::clmm::clmm_position_fees::fetch_position_accrual;
+use crate::instructions::clmm::clmm_price::{
+    price_range_to_ticks, price_to_sqrt_price_x64, sqrt_price_x64_to_price, tick_to_price,
+};
+use crate::instructions::clmm::clmm_quote::{SwapAmount, default_sqrt_price_limit};
+use crate::instructions::clmm::clmm_rebalance::{RebalanceConfig, clmm_rebalance_once};
+use crate::instructions::clmm::clmm_reward::RewardSchedule;
+use crate::instructions::clmm::clmm_set_reward_params::clmm_set_reward_params;
+use crate::instructions::clmm::clmm_swap::clmm_swap;
+use crate::instructions::clmm::clmm_swap_fixture::SwapFixture;
+use crate::instructions::clmm::clmm_wallet_positions::{PositionSummary, fetch_position_summaries};
+use crate::instructions::clmm::liquidity_math::{
+    get_delta_amounts_signed, get_liquidity_from_amounts,
+};
+use crate::instructions::clmm::tick_math::{
+    TickRange, get_sqrt_price_at_tick, get_tick_at_sqrt_price,
+};
+use crate::instructions::cp_amm::cp_amm_atomic_deposit_withdraw::cp_amm_atomic_deposit_then_withdraw;
+use crate::instructions::cp_amm::cp_amm_atomic_withdraw_deposit::cp_amm_atomic_withdraw_then_deposit;
+use crate::instructions::cp_amm::cp_amm_config::{AmmConfigSelector, fetch_all_amm_configs};
+use crate::instructions::cp_amm::cp_amm_create_pool::cp_amm_create_pool;
+use crate::instructions::cp_amm::cp_amm_deposit_liquidity::{
+    cp_amm_deposit_liquidity, cp_amm_deposit_liquidity_by_token_amount,
+};
+use crate::instructions::cp_amm::cp_amm_pool::{CpAmmPool, RAYDIUM_CP_SWAP_PROGRAM_ID};
+use crate::instructions::cp_amm::cp_amm_swap_base_input::cp_amm_swap_base_input;
+use crate::instructions::cp_amm::cp_amm_swap_base_output::cp_amm_swap_base_output;
+use crate::instructions::cp_amm::cp_amm_withdraw_liquidity::cp_amm_withdraw_liquidity;
+use crate::utils::program_utils::fetch_accounts_and_signatures;
+use crate::utils::signer_utils::{SignerSource, load_signer};
+use crate::utils::slippage::Slippage;
+
+#[derive(Parser, Debug)]
+#[command(about = "Raydium CP-AMM and CLMM liquidity management")]
+pub struct Cli {
+    /// JSON RPC endpoint of the cluster to use.
+    #[arg(
+        long,
+        short = 'u',
+        global = true,
+        default_value = "https://api.devnet.solana.com"
+    )]
+    pub url: String,
+
+    /// Solana CLI keypair file; defaults to ~/.config/solana/id.json.
+    #[arg(long, short = 'k', global = true, conflicts_with = "keypair_env")]
+    pub keypair: Option<PathBuf>,
+
+    /// Environment variable holding a base58 secret key.
+    #[arg(long, global = true)]
+    pub keypair_env: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Constant-product AMM operations.
+    #[command(name = "cp-amm", subcommand)]
+    CpAmm(CpAmmCommand),
+    /// Concentrated-liquidity AMM operations.
+    #[command(subcommand)]
+    Clmm(ClmmCommand),
+    /// Program account inspection.
+    #[command(subcommand)]
+    Accounts(AccountsCommand),
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CpAmmCommand {
+    /// Create a pool for two mints and seed it with initial liquidity.
+    CreatePool {
+        #[arg(long)]
+        mint_a: Pubkey,
+        #[arg(long)]
+        mint_b: Pubkey,
+        #[arg(long)]
+        amount_a: u64,
+        #[arg(long)]
+        amount_b: u64,
+        /// AmmConfig index to create the pool under.
+        #[arg(long, conflicts_with = "trade_fee_rate")]
+        amm_config_index: Option<u16>,
+        /// Pick the AmmConfig with this trade fee rate (parts per million).
+        #[arg(long)]
+        trade_fee_rate: Option<u64>,
+    },
+    /// List every AmmConfig fee tier.
+    ListConfigs,
+    /// Deposit liquidity, either by LP amount or by token amounts.
+    Deposit {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long, required_unless_present_any = ["token_0_amount", "token_1_amount"])]
+        lp_amount: Option<u64>,
+        /// Most token 0 to spend, transfer fees included.
+        #[arg(long, conflicts_with = "lp_amount")]
+        token_0_amount: Option<u64>,
+        /// Most token 1 to spend, transfer fees included.
+        #[arg(long, conflicts_with = "lp_amount")]
+        token_1_amount: Option<u64>,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Burn LP tokens for the underlying tokens.
+    Withdraw {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        lp_amount: u64,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Deposit and withdraw the same LP amount in one transaction.
+    AtomicDepositWithdraw {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        lp_amount: u64,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Withdraw and re-deposit the same LP amount in one transaction.
+    AtomicWithdrawDeposit {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        lp_amount: u64,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Swap an exact amount of `input-mint`.
+    SwapBaseInput {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        input_mint: Pubkey,
+        #[arg(long)]
+        amount_in: u64,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Buy an exact amount of `output-mint`.
+    SwapBaseOutput {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        output_mint: Pubkey,
+        #[arg(long)]
+        amount_out: u64,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClmmCommand {
+    /// Create a CLMM pool for two mints at an initial price.
+    CreatePool {
+        #[arg(long, default_value = "CQYbhr6amxUER4p5SC44C63R4qw4NFc9Z4Db9vF4tZwG")]
+        amm_config: Pubkey,
+        #[arg(long)]
+        mint_a: Pubkey,
+        #[arg(long)]
+        mint_b: Pubkey,
+        /// Units of mint B one unit of mint A is worth, decimals applied.
+        #[arg(long)]
+        price: f64,
+    },
+    /// Show a pool's state, fee tier and latest observation.
+    Pool {
+        #[arg(long)]
+        pool: Pubkey,
+    },
+    /// Show a position's range, current amounts and uncollected fees and
+    /// rewards.
+    Position {
+        /// Mint of the position NFT.
+        #[arg(long)]
+        position: Pubkey,
+    },
+    /// List every position a wallet holds, across all pools.
+    Positions {
+        /// Wallet to scan; defaults to the signer.
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+    /// Convert between a price, its Q64.64 sqrt price and its tick.
+    Price {
+        /// Token 1 per token 0, decimals applied.
+        #[arg(long, required_unless_present_any = ["sqrt_price_x64", "tick"], conflicts_with_all = ["sqrt_price_x64", "tick"])]
+        price: Option<f64>,
+        #[arg(long, conflicts_with = "tick")]
+        sqrt_price_x64: Option<u128>,
+        #[arg(long, allow_hyphen_values = true)]
+        tick: Option<i32>,
+        #[arg(long)]
+        decimals_0: u8,
+        #[arg(long)]
+        decimals_1: u8,
+    },
+    /// Quote the liquidity and token amounts of a position over a price
+    /// range, with the range widened to the nearest usable ticks.
+    LiquidityQuote {
+        /// Current price, token 1 per token 0 with decimals applied.
+        #[arg(long)]
+        price: f64,
+        #[arg(long)]
+        lower_price: f64,
+        #[arg(long)]
+        upper_price: f64,
+        #[arg(long)]
+        tick_spacing: u16,
+        #[arg(long)]
+        decimals_0: u8,
+        #[arg(long)]
+        decimals_1: u8,
+        #[arg(long, required_unless_present_any = ["amount_0", "amount_1"], conflicts_with_all = ["amount_0", "amount_1"])]
+        liquidity: Option<u128>,
+        /// Most token 0 to put in, in base units.
+        #[arg(long)]
+        amount_0: Option<u64>,
+        /// Most token 1 to put in, in base units.
+        #[arg(long)]
+        amount_1: Option<u64>,
+    },
+    /// Open a position over a price range, minting its NFT under Token-2022.
+    OpenPosition {
+        #[arg(long)]
+        pool: Pubkey,
+        /// Token 1 per token 0, decimals applied; rounded down to a usable
+        /// tick.
+        #[arg(long)]
+        lower_price: f64,
+        /// Rounded up to a usable tick.
+        #[arg(long)]
+        upper_price: f64,
+        /// Most token 0 to spend, transfer fees included.
+        #[arg(long, required_unless_present = "amount_1")]
+        amount_0: Option<u64>,
+        /// Most token 1 to spend, transfer fees included.
+        #[arg(long)]
+        amount_1: Option<u64>,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Add liquidity to an existing position over its current range.
+    IncreaseLiquidity {
+        /// Mint of the position NFT.
+        #[arg(long)]
+        position: Pubkey,
+        /// Most token 0 to spend, transfer fees included.
+        #[arg(long, required_unless_present = "amount_1")]
+        amount_0: Option<u64>,
+        /// Most token 1 to spend, transfer fees included.
+        #[arg(long)]
+        amount_1: Option<u64>,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Remove liquidity from a position, collecting its fees and rewards.
+    DecreaseLiquidity {
+        /// Mint of the position NFT.
+        #[arg(long)]
+        position: Pubkey,
+        #[arg(long, required_unless_present = "all", conflicts_with = "all")]
+        liquidity: Option<u128>,
+        /// Remove all of the position's liquidity.
+        #[arg(long)]
+        all: bool,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Swap on a CLMM pool, either an exact input or an exact output.
+    Swap {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        input_mint: Pubkey,
+        /// Exact amount of `input-mint` to spend, transfer fee included.
+        #[arg(
+            long,
+            required_unless_present = "amount_out",
+            conflicts_with = "amount_out"
+        )]
+        amount_in: Option<u64>,
+        /// Exact amount of the other token to receive, after transfer fees.
+        #[arg(long)]
+        amount_out: Option<u64>,
+        /// Furthest Q64.64 sqrt price the swap may move the pool to.
+        #[arg(long)]
+        sqrt_price_limit_x64: Option<u128>,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Simulate a swap offline, against live pool state or a saved fixture.
+    SimulateSwap {
+        /// Replay a fixture instead of reading the pool.
+        #[arg(long, conflicts_with_all = ["pool", "input_mint", "amount_in", "amount_out", "sqrt_price_limit_x64", "save_fixture"])]
+        fixture: Option<PathBuf>,
+        #[arg(long, required_unless_present = "fixture", requires = "input_mint")]
+        pool: Option<Pubkey>,
+        #[arg(long)]
+        input_mint: Option<Pubkey>,
+        /// Exact amount entering the pool, net of transfer fees.
+        #[arg(long, required_unless_present_any = ["fixture", "amount_out"], conflicts_with = "amount_out")]
+        amount_in: Option<u64>,
+        /// Exact amount leaving the pool, before transfer fees.
+        #[arg(long)]
+        amount_out: Option<u64>,
+        #[arg(long)]
+        sqrt_price_limit_x64: Option<u128>,
+        /// Save the captured state and swap as a fixture.
+        #[arg(long)]
+        save_fixture: Option<PathBuf>,
+    },
+    /// Close an empty position, burning its NFT and reclaiming rent.
+    ClosePosition {
+        /// Mint of the position NFT.
+        #[arg(long)]
+        position: Pubkey,
+        /// Remove any remaining liquidity, fees and rewards in the same
+        /// transaction.
+        #[arg(long)]
+        collect: bool,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Keep a position around the current price, closing and reopening it
+    /// whenever the price leaves or nears the edge of its range.
+    Rebalance {
+        /// Mint of the position NFT to start from.
+        #[arg(long)]
+        position: Pubkey,
+        /// Width of each new position in ticks, rounded up to the pool's tick
+        /// spacing.
+        #[arg(long)]
+        width_ticks: i32,
+        /// Also rebalance once the price is this many ticks from an edge.
+        #[arg(long, default_value_t = 0)]
+        edge_ticks: i32,
+        /// Swap the withdrawn tokens to the new range's ratio before
+        /// reopening.
+        #[arg(long)]
+        swap: bool,
+        /// Log the planned transaction without sending it.
+        #[arg(long)]
+        dry_run: bool,
+        /// Lookup table to use when the rebalance is too large for a legacy
+        /// transaction; one is created when needed otherwise.
+        #[arg(long)]
+        lookup_table: Option<Pubkey>,
+        /// Seconds between checks.
+        #[arg(long, default_value_t = 30)]
+        interval_secs: u64,
+        /// Check once and exit instead of watching.
+        #[arg(long)]
+        once: bool,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Sell a token at just beyond the current price with a single-sided
+    /// position one tick spacing wide.
+    PlaceLimitOrder {
+        #[arg(long)]
+        pool: Pubkey,
+        /// Token to sell.
+        #[arg(long)]
+        sell_mint: Pubkey,
+        /// Most of `sell-mint` to sell, transfer fees included.
+        #[arg(long)]
+        amount: u64,
+        /// File the orders are kept in.
+        #[arg(long, default_value = "limit_orders.json")]
+        orders: PathBuf,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// List limit orders with their status against the current price.
+    LimitOrders {
+        #[arg(long, default_value = "limit_orders.json")]
+        orders: PathBuf,
+    },
+    /// Cancel a limit order, closing its position with whatever it holds.
+    CancelLimitOrder {
+        /// Mint of the order's position NFT.
+        #[arg(long)]
+        position: Pubkey,
+        #[arg(long, default_value = "limit_orders.json")]
+        orders: PathBuf,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Watch open limit orders and close each once the price has crossed it.
+    WatchLimitOrders {
+        #[arg(long, default_value = "limit_orders.json")]
+        orders: PathBuf,
+        /// Seconds between checks.
+        #[arg(long, default_value_t = 30)]
+        interval_secs: u64,
+        /// Check once and exit instead of watching.
+        #[arg(long)]
+        once: bool,
+        #[command(flatten)]
+        slippage: SlippageArgs,
+    },
+    /// Open a reward stream on a pool, funding the whole schedule up front.
+    InitializeReward {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        reward_mint: Pubkey,
+        /// Unix time the reward starts emitting.
+        #[arg(long)]
+        open_time: u64,
+        /// Unix time the reward stops emitting.
+        #[arg(long)]
+        end_time: u64,
+        /// Total reward to emit over the schedule, in base units.
+        #[arg(
+            long,
+            required_unless_present = "emissions_per_second_x64",
+            conflicts_with = "emissions_per_second_x64"
+        )]
+        amount: Option<u64>,
+        /// Base units emitted per second, as Q64.64.
+        #[arg(long)]
+        emissions_per_second_x64: Option<u128>,
+    },
+    /// Restart an ended reward stream or extend a running one.
+    SetRewardParams {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        reward_mint: Pubkey,
+        /// New open time, for restarting an ended reward.
+        #[arg(long)]
+        open_time: Option<u64>,
+        #[arg(long)]
+        end_time: u64,
+        /// New emission rate as Q64.64; defaults to the current one.
+        #[arg(long)]
+        emissions_per_second_x64: Option<u128>,
+    },
+    /// Withdraw what is left in an ended reward stream's vault.
+    CollectRemainingRewards {
+        #[arg(long)]
+        pool: Pubkey,
+        #[arg(long)]
+        reward_mint: Pubkey,
+    },
+    /// Collect fees and rewards from all of the wallet's positions in a pool.
+    CollectRewards {
+        #[arg(long)]
+        pool: Pubkey,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AccountsCommand {
+    /// List accounts owned by a program with their recent transactions.
+    Scan {
+        /// Program to scan; defaults to the CP-AMM program.
+        #[arg(long, default_value = RAYDIUM_CP_SWAP_PROGRAM_ID)]
+        program: String,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct SlippageArgs {
+    /// Slippage tolerance in basis points.
+    #[arg(long, conflicts_with = "slippage_amount")]
+    pub slippage_bps: Option<u64>,
+    /// Slippage tolerance as a fixed number of base units.
+    #[arg(long)]
+    pub slippage_amount: Option<u64>,
+}
+
+impl From<&SlippageArgs> for Slippage {
+    fn from(args: &SlippageArgs) -> Self {
+        match (args.slippage_bps, args.slippage_amount) {
+            (_, Some(amount)) => Slippage::Absolute(amount),
+            (Some(bps), None) => Slippage::Bps(bps),
+            (None, None) => Slippage::default(),
+        }
+    }
+}
+
+impl Cli {
+    fn signer_source(&self) -> SignerSource {
+        match (&self.keypair, &self.keypair_env) {
+            (Some(path), _) => SignerSource::File(path.clone()),
+            (None, Some(var)) => SignerSource::Env(var.clone()),
+            (None, None) => SignerSource::Default,
+        }
+    }
+}
+
+fn explorer_url(signature: &Signature, rpc_url: &str) -> String {
+    let cluster = if rpc_url.contains("devnet") {
+        "?cluster=devnet"
+    } else if rpc_url.contains("testnet") {
+        "?cluster=testnet"
+    } else {
+        ""
+    };
+    format!("https://explorer.solana.com/tx/{}{}", signature, cluster)
+}
+
+fn report_signature(signature: &Signature, rpc_url: &str) {
+    println!("✅ Transaction Signature: {}", signature);
+    println!("   Solana Explorer: {}", explorer_url(signature, rpc_url));
+}
+
+pub fn run(cli: Cli) -> Result<()> {
+    let rpc_client = RpcClient::new(cli.url.clone());
+
+    match &cli.command {
+        Command::CpAmm(CpAmmCommand::ListConfigs) => list_amm_configs(&rpc_client),
+        Command::CpAmm(command) => {
+            let user = load_signer(&cli.signer_source())?;
+            let signature = run_cp_amm(&rpc_client, &*user, command)?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::CreatePool {
+            amm_config,
+            mint_a,
+            mint_b,
+            price,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let (signature, addresses) =
+                clmm_create_pool(&rpc_client, &*user, amm_config, mint_a, mint_b, *price)?;
+            println!("Pool State: {}", addresses.pool_state);
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::Price {
+            price,
+            sqrt_price_x64,
+            tick,
+            decimals_0,
+            decimals_1,
+        }) => {
+            let sqrt_price_x64 = match (price, sqrt_price_x64, tick) {
+                (Some(price), _, _) => price_to_sqrt_price_x64(*price, *decimals_0, *decimals_1)?,
+                (None, Some(sqrt_price_x64), _) => *sqrt_price_x64,
+                (None, None, Some(tick)) => {
+                    println!("Tick: {}", tick);
+                    println!("Price: {}", tick_to_price(*tick, *decimals_0, *decimals_1)?);
+                    return Ok(());
+                }
+                (None, None, None) => unreachable!("clap requires one of the three"),
+            };
+            println!("sqrt_price_x64: {}", sqrt_price_x64);
+            println!("Tick: {}", get_tick_at_sqrt_price(sqrt_price_x64)?);
+            println!(
+                "Price: {}",
+                sqrt_price_x64_to_price(sqrt_price_x64, *decimals_0, *decimals_1)
+            );
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::Pool { pool }) => print_clmm_pool(&rpc_client, pool),
+        Command::Clmm(ClmmCommand::Position { position }) => {
+            print_clmm_position(&rpc_client, position)
+        }
+        Command::Clmm(ClmmCommand::Positions { owner }) => {
+            let owner = match owner {
+                Some(owner) => *owner,
+                None => load_signer(&cli.signer_source())?.pubkey(),
+            };
+            list_clmm_positions(&rpc_client, &owner)
+        }
+        Command::Clmm(command @ ClmmCommand::LiquidityQuote { .. }) => {
+            print_liquidity_quote(command)
+        }
+        Command::Clmm(ClmmCommand::OpenPosition {
+            pool,
+            lower_price,
+            upper_price,
+            amount_0,
+            amount_1,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut pool = ClmmPool::load(&rpc_client, pool, &user.pubkey())?;
+            let tick_range = price_range_to_ticks(
+                *lower_price,
+                *upper_price,
+                pool.state.mint_decimals_0,
+                pool.state.mint_decimals_1,
+                pool.state.tick_spacing,
+            )?;
+            let (signature, position) = clmm_open_position(
+                &rpc_client,
+                &*user,
+                &mut pool,
+                tick_range,
+                *amount_0,
+                *amount_1,
+                slippage.into(),
+            )?;
+            println!("Position NFT Mint: {}", position.position_nft_mint);
+            println!("Personal Position: {}", position.personal_position);
+            println!(
+                "Ticks: [{}, {})",
+                position.tick_range.tick_lower, position.tick_range.tick_upper
+            );
+            println!("Liquidity: {}", position.liquidity);
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::IncreaseLiquidity {
+            position,
+            amount_0,
+            amount_1,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let (mut pool, position) = load_clmm_position(&rpc_client, position, &*user)?;
+            let (signature, liquidity) = clmm_increase_liquidity(
+                &rpc_client,
+                &*user,
+                &mut pool,
+                &position,
+                *amount_0,
+                *amount_1,
+                slippage.into(),
+            )?;
+            println!("Liquidity Added: {}", liquidity);
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::DecreaseLiquidity {
+            position,
+            liquidity,
+            all: _,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let (mut pool, position) = load_clmm_position(&rpc_client, position, &*user)?;
+            let signature = clmm_decrease_liquidity(
+                &rpc_client,
+                &*user,
+                &mut pool,
+                &position,
+                *liquidity,
+                slippage.into(),
+            )?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::Swap {
+            pool,
+            input_mint,
+            amount_in,
+            amount_out,
+            sqrt_price_limit_x64,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut pool = ClmmPool::load(&rpc_client, pool, &user.pubkey())?;
+            let amount = match (amount_in, amount_out) {
+                (Some(amount_in), _) => SwapAmount::ExactIn(*amount_in),
+                (None, Some(amount_out)) => SwapAmount::ExactOut(*amount_out),
+                (None, None) => unreachable!("clap requires one of the two"),
+            };
+            let signature = clmm_swap(
+                &rpc_client,
+                &*user,
+                &mut pool,
+                input_mint,
+                amount,
+                *sqrt_price_limit_x64,
+                slippage.into(),
+            )?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(command @ ClmmCommand::SimulateSwap { .. }) => {
+            simulate_clmm_swap(&rpc_client, command)
+        }
+        Command::Clmm(ClmmCommand::ClosePosition {
+            position,
+            collect,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let (mut pool, position) = load_clmm_position(&rpc_client, position, &*user)?;
+            let (signature, reclaimed_lamports) = clmm_close_position(
+                &rpc_client,
+                &*user,
+                &mut pool,
+                &position,
+                *collect,
+                slippage.into(),
+            )?;
+            println!("Reclaimed Lamports: {}", reclaimed_lamports);
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::Rebalance {
+            position,
+            width_ticks,
+            edge_ticks,
+            swap,
+            dry_run,
+            lookup_table,
+            interval_secs,
+            once,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut config = RebalanceConfig {
+                width_ticks: *width_ticks,
+                edge_ticks: *edge_ticks,
+                swap_to_ratio: *swap,
+                slippage: slippage.into(),
+                dry_run: *dry_run,
+                lookup_table: *lookup_table,
+            };
+            let (mut pool, position) = load_clmm_position(&rpc_client, position, &*user)?;
+            // Settings that cannot get better by retrying fail straight away.
+            pool.ensure_owner(&user.pubkey())?;
+            position.ensure_pool(&pool.pool_id)?;
+            config.slippage.validate()?;
+            let mut tracked = position.nft_mint;
+            loop {
+                let rebalanced = ClmmPosition::load(&rpc_client, &tracked, &user.pubkey())
+                    .and_then(|position| {
+                        clmm_rebalance_once(&rpc_client, &*user, &mut pool, &position, &config)
+                    });
+                match rebalanced {
+                    Ok(Some(rebalance)) => {
+                        for signature in &rebalance.signatures {
+                            report_signature(signature, &cli.url);
+                        }
+                        println!(
+                            "Now tracking position {}",
+                            rebalance.opened.position_nft_mint
+                        );
+                        config.lookup_table = rebalance.lookup_table.or(config.lookup_table);
+                        tracked = rebalance.opened.position_nft_mint;
+                    }
+                    Ok(None) => {}
+                    Err(err) if *once => return Err(err),
+                    Err(err) => println!("Rebalance failed, retrying next interval: {}", err),
+                }
+                if *once {
+                    return Ok(());
+                }
+                std::thread::sleep(Duration::from_secs(*interval_secs));
+            }
+        }
+        Command::Clmm(ClmmCommand::PlaceLimitOrder {
+            pool,
+            sell_mint,
+            amount,
+            orders,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut pool = ClmmPool::load(&rpc_client, pool, &user.pubkey())?;
+            let order = clmm_place_limit_order(
+                &rpc_client,
+                &*user,
+                &mut pool,
+                sell_mint,
+                *amount,
+                slippage.into(),
+            )?;
+            println!("Position NFT Mint: {}", order.position_nft_mint);
+            let signature = Signature::from_str(&order.open_signature)?;
+            // Reload, as the watcher may have saved the book meanwhile.
+            let mut book = LimitOrderBook::load(orders)?;
+            book.orders.push(order);
+            book.save(orders)?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::LimitOrders { orders }) => {
+            list_limit_orders(&rpc_client, &LimitOrderBook::load(orders)?)
+        }
+        Command::Clmm(ClmmCommand::CancelLimitOrder {
+            position,
+            orders,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut order = LimitOrderBook::load(orders)?.find_mut(position)?.clone();
+            let signature = clmm_close_limit_order(
+                &rpc_client,
+                &*user,
+                &mut order,
+                LimitOrderStatus::Cancelled,
+                slippage.into(),
+            )?;
+            // Reload, as the watcher may have saved the book meanwhile.
+            let mut book = LimitOrderBook::load(orders)?;
+            *book.find_mut(position)? = order;
+            book.save(orders)?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::WatchLimitOrders {
+            orders,
+            interval_secs,
+            once,
+            slippage,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            loop {
+                let pass = clmm_settle_limit_orders(&rpc_client, &*user, orders, slippage.into())?;
+                for signature in &pass.signatures {
+                    report_signature(signature, &cli.url);
+                }
+                if *once || pass.active_orders == 0 {
+                    return Ok(());
+                }
+                std::thread::sleep(Duration::from_secs(*interval_secs));
+            }
+        }
+        Command::Clmm(ClmmCommand::InitializeReward {
+            pool,
+            reward_mint,
+            open_time,
+            end_time,
+            amount,
+            emissions_per_second_x64,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut pool = ClmmPool::load(&rpc_client, pool, &user.pubkey())?;
+            let schedule = match (amount, emissions_per_second_x64) {
+                (Some(amount), _) => {
+                    RewardSchedule::from_total_amount(*open_time, *end_time, *amount)?
+                }
+                (None, Some(emissions_per_second_x64)) => RewardSchedule {
+                    open_time: *open_time,
+                    end_time: *end_time,
+                    emissions_per_second_x64: *emissions_per_second_x64,
+                },
+                (None, None) => unreachable!("clap requires one of the two"),
+            };
+            let signature =
+                clmm_initialize_reward(&rpc_client, &*user, &mut pool, reward_mint, schedule)?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::SetRewardParams {
+            pool,
+            reward_mint,
+            open_time,
+            end_time,
+            emissions_per_second_x64,
+        }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut pool = ClmmPool::load(&rpc_client, pool, &user.pubkey())?;
+            let signature = clmm_set_reward_params(
+                &rpc_client,
+                &*user,
+                &mut pool,
+                reward_mint,
+                *open_time,
+                *end_time,
+                *emissions_per_second_x64,
+            )?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::CollectRemainingRewards { pool, reward_mint }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut pool = ClmmPool::load(&rpc_client, pool, &user.pubkey())?;
+            let signature =
+                clmm_collect_remaining_rewards(&rpc_client, &*user, &mut pool, reward_mint)?;
+            report_signature(&signature, &cli.url);
+            Ok(())
+        }
+        Command::Clmm(ClmmCommand::CollectRewards { pool }) => {
+            let user = load_signer(&cli.signer_source())?;
+            let mut pool = ClmmPool::load(&rpc_client, pool, &user.pubkey())?;
+            for signature in clmm_collect_rewards(&rpc_client, &*user, &mut pool)? {
+                report_signature(&signature, &cli.url);
+            }
+            Ok(())
+        }
+        Command::Accounts(AccountsCommand::Scan { program }) => {
+            let runtime = tokio::runtime::Runtime::new()?;
+            runtime.block_on(fetch_accounts_and_signatures(program, &cli.url));
+            Ok(())
+        }
+    }
+}
+
+fn simulate_clmm_swap(rpc_client: &RpcClient, command: &ClmmCommand) -> Result<()> {
+    let ClmmCommand::SimulateSwap {
+        ref fixture,
+        pool,
+        input_mint,
+        amount_in,
+        amount_out,
+        sqrt_price_limit_x64,
+        ref save_fixture,
+    } = *command
+    else {
+        unreachable!("only called for swap simulations");
+    };
+
+    let fixture = match (fixture, pool, input_mint) {
+        (Some(path), _, _) => SwapFixture::load(path)?,
+        (None, Some(pool_id), Some(input_mint)) => {
+            // Nothing is signed, so any owner will do.
+            let pool = ClmmPool::load(rpc_client, &pool_id, &Pubkey::default())?;
+            let zero_for_one = pool.is_token_0(&input_mint)?;
+            let sqrt_price_limit_x64 =
+                sqrt_price_limit_x64.unwrap_or_else(|| default_sqrt_price_limit(zero_for_one));
+            let fixture = SwapFixture::capture(
+                rpc_client,
+                &pool,
+                zero_for_one,
+                amount_in.or(amount_out).expect("clap requires an amount"),
+                amount_in.is_some(),
+                sqrt_price_limit_x64,
+            )?;
+            if let Some(path) = save_fixture {
+                fixture.save(path)?;
+                println!("Saved fixture to {}", path.display());
+            }
+            fixture
+        }
+        _ => unreachable!("clap requires a fixture or a pool and input mint"),
+    };
+
+    let simulation = fixture.simulate()?;
+    println!(
+        "Direction: {}",
+        if fixture.zero_for_one {
+            "token 0 -> token 1"
+        } else {
+            "token 1 -> token 0"
+        }
+    );
+    println!("Amount In: {}", simulation.amount_in);
+    println!("Amount Out: {}", simulation.amount_out);
+    println!("Trade Fee: {}", simulation.fee_amount);
+    if simulation.amount_remaining != 0 {
+        println!(
+            "Unfilled: {} (stopped at the price limit)",
+            simulation.amount_remaining
+        );
+    }
+    println!(
+        "Final sqrt_price_x64: {} (tick {}, liquidity {})",
+        simulation.sqrt_price_x64, simulation.tick_current, simulation.liquidity
+    );
+    println!("Tick Arrays Touched: {:?}", simulation.tick_arrays_touched);
+
+    if fixture.expected.is_some() {
+        let mismatches = fixture.mismatches(&simulation);
+        if !mismatches.is_empty() {
+            return Err(format_err!(
+                "Simulation disagrees with the fixture:\n  {}",
+                mismatches.join("\n  ")
+            ));
+        }
+        println!("✅ Simulation matches the fixture");
+    }
+    Ok(())
+}
+
+/// Loads the position behind `nft_mint` and the pool it belongs to.
+fn load_clmm_position(
+    rpc_client: &RpcClient,
+    nft_mint: &Pubkey,
+    user: &dyn Signer,
+) -> Result<(ClmmPool, ClmmPosition)> {
+    let position = ClmmPosition::load(rpc_client, nft_mint, &user.pubkey())?;
+    let pool = ClmmPool::load(rpc_client, &position.state.pool_id, &user.pubkey())?;
+    Ok((pool, position))
+}
+
+fn list_amm_configs(rpc_client: &RpcClient) -> Result<()> {
+    for (address, amm_config) in fetch_all_amm_configs(rpc_client)? {
+        println!(
+            "[{}] {} trade: {} protocol: {} fund: {} create fee: {}{}",
+            amm_config.index,
+            address,
+            amm_config.trade_fee_rate,
+            amm_config.protocol_fee_rate,
+            amm_config.fund_fee_rate,
+            amm_config.create_pool_fee,
+            if amm_config.disable_create_pool {
+                " (create disabled)"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+fn print_clmm_pool(rpc_client: &RpcClient, pool_id: &Pubkey) -> Result<()> {
+    let pool_state = fetch_pool_state(rpc_client, pool_id)?;
+    let amm_config = fetch_amm_config(rpc_client, &pool_state.amm_config)?;
+    let observation_state = fetch_observation_state(rpc_client, &pool_state.observation_key)?;
+    let fee_percent = |rate: u32| rate as f64 / FEE_RATE_DENOMINATOR_VALUE as f64 * 100.0;
+
+    println!("Pool: {}", pool_id);
+    println!(
+        "AmmConfig: {} (index {}, tick spacing {})",
+        pool_state.amm_config, amm_config.index, amm_config.tick_spacing
+    );
+    println!(
+        "   - Trade Fee: {}%, Protocol Share: {}%, Fund Share: {}%",
+        fee_percent(amm_config.trade_fee_rate),
+        fee_percent(amm_config.protocol_fee_rate),
+        fee_percent(amm_config.fund_fee_rate)
+    );
+    println!(
+        "Token 0: {} ({} decimals), vault {}",
+        pool_state.token_mint_0, pool_state.mint_decimals_0, pool_state.token_vault_0
+    );
+    println!(
+        "Token 1: {} ({} decimals), vault {}",
+        pool_state.token_mint_1, pool_state.mint_decimals_1, pool_state.token_vault_1
+    );
+    println!("Price: {} token 1 per token 0", pool_state.price());
+    println!(
+        "Tick: {}, sqrt_price_x64: {}",
+        pool_state.tick_current, pool_state.sqrt_price_x64
+    );
+    println!("Liquidity: {}", pool_state.liquidity);
+    println!(
+        "Fee Growth: {} token 0, {} token 1 (Q64.64 per unit of liquidity)",
+        pool_state.fee_growth_global_0_x64, pool_state.fee_growth_global_1_x64
+    );
+    println!(
+        "Unclaimed Protocol Fees: {} token 0, {} token 1",
+        pool_state.protocol_fees_token_0, pool_state.protocol_fees_token_1
+    );
+    println!(
+        "Unclaimed Fund Fees: {} token 0, {} token 1",
+        pool_state.fund_fees_token_0, pool_state.fund_fees_token_1
+    );
+    println!(
+        "Initialized Tick Arrays Near Price: {}",
+        pool_state
+            .tick_array_bitmap
+            .iter()
+            .map(|word| word.count_ones())
+            .sum::<u32>()
+    );
+    for (index, reward_info) in pool_state.reward_infos.iter().enumerate() {
+        if !reward_info.initialized() {
+            continue;
+        }
+        println!(
+            "Reward {}: {} ({:?}), {} to {}, {} per second (Q64.64), authority {}",
+            index,
+            reward_info.token_mint,
+            reward_info.state(),
+            reward_info.open_time,
+            reward_info.end_time,
+            reward_info.emissions_per_second_x64,
+            reward_info.authority
+        );
+    }
+    if pool_state
+        .reward_infos
+        .iter()
+        .all(|reward_info| !reward_info.initialized())
+    {
+        println!("Rewards: none");
+    }
+
+    let observation = observation_state.latest();
+    println!(
+        "Latest Observation: #{} at {}, tick cumulative {}",
+        observation_state.observation_index,
+        observation.block_timestamp,
+        observation.tick_cumulative
+    );
+    Ok(())
+}
+
+fn list_clmm_positions(rpc_client: &RpcClient, owner: &Pubkey) -> Result<()> {
+    let summaries = fetch_position_summaries(rpc_client, owner)?;
+    println!("Found {} positions for {}", summaries.len(), owner);
+    for summary in summaries {
+        println!(
+            "{} in pool {}: ticks [{}, {}), liquidity {}, {} token 0, {} token 1{}",
+            summary.nft_mint,
+            summary.pool_id,
+            summary.tick_range.tick_lower,
+            summary.tick_range.tick_upper,
+            summary.liquidity,
+            summary.amount_0,
+            summary.amount_1,
+            if summary.in_range {
+                ""
+            } else {
+                " (out of range)"
+            }
+        );
+    }
+    Ok(())
+}
+
+fn list_limit_orders(rpc_client: &RpcClient, book: &LimitOrderBook) -> Result<()> {
+    if book.orders.is_empty() {
+        println!("No limit orders");
+    }
+    for order in &book.orders {
+        let mut status = format!("{:?}", order.status);
+        if order.status.is_active() {
+            let tick_current = fetch_pool_state(rpc_client, &order.pool_id)?.tick_current;
+            let crossing_status = order.crossing_status(tick_current);
+            status = format!("{:?} at tick {}", crossing_status, tick_current);
+            if crossing_status == LimitOrderStatus::Filled {
+                status.push_str(", awaiting close");
+            }
+        }
+        println!(
+            "{} in pool {}: {:?} {} over ticks [{}, {}), {}",
+            order.position_nft_mint,
+            order.pool_id,
+            order.side,
+            order.amount,
+            order.tick_lower,
+            order.tick_upper,
+            status
+        );
+    }
+    Ok(())
+}
+
+fn print_clmm_position(rpc_client: &RpcClient, nft_mint: &Pubkey) -> Result<()> {
+    let program_id = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?;
+    let position = fetch_personal_position(rpc_client, nft_mint)?;
+    let pool_state = fetch_pool_state(rpc_client, &position.pool_id)?;
+    let accrual = fetch_position_accrual(
+        rpc_client,
+        &program_id,
+        &position.pool_id,
+        &pool_state,
+        &position,
+    )?;
+    let summary = PositionSummary::new(nft_mint, &position, &pool_state)?;
+    let TickRange {
+        tick_lower,
+        tick_upper,
+    } = summary.tick_range;
+
+    println!("Position: {}", nft_mint);
+    println!("Pool: {}", summary.pool_id);
+    println!(
+        "Range: ticks [{}, {}), prices [{}, {})",
+        tick_lower,
+        tick_upper,
+        tick_to_price(
+            tick_lower,
+            pool_state.mint_decimals_0,
+            pool_state.mint_decimals_1
+        )?,
+        tick_to_price(
+            tick_upper,
+            pool_state.mint_decimals_0,
+            pool_state.mint_decimals_1
+        )?
+    );
+    println!(
+        "Current Tick: {} ({})",
+        summary.tick_current,
+        if summary.in_range {
+            "in range"
+        } else {
+            "out of range"
+        }
+    );
+    println!("Liquidity: {}", summary.liquidity);
+    println!(
+        "Amounts: {} token 0, {} token 1",
+        summary.amount_0, summary.amount_1
+    );
+    println!(
+        "Uncollected Fees: {} token 0, {} token 1",
+        accrual.fees_owed_0, accrual.fees_owed_1
+    );
+    for (reward_mint, amount) in &accrual.rewards_owed {
+        println!("Uncollected Reward: {} of {}", amount, reward_mint);
+    }
+    if accrual.rewards_owed.is_empty() {
+        println!("Rewards: none");
+    }
+    Ok(())
+}
+
+fn print_liquidity_quote(command: &ClmmCommand) -> Result<()> {
+    let ClmmCommand::LiquidityQuote {
+        price,
+        lower_price,
+        upper_price,
+        tick_spacing,
+        decimals_0,
+        decimals_1,
+        liquidity,
+        amount_0,
+        amount_1,
+    } = *command
+    else {
+        unreachable!("only called for liquidity quotes");
+    };
+    let sqrt_price_x64 = price_to_sqrt_price_x64(price, decimals_0, decimals_1)?;
+    let tick_current = get_tick_at_sqrt_price(sqrt_price_x64)?;
+    let TickRange {
+        tick_lower,
+        tick_upper,
+    } = price_range_to_ticks(
+        lower_price,
+        upper_price,
+        decimals_0,
+        decimals_1,
+        tick_spacing,
+    )?;
+
+    let liquidity = match liquidity {
+        Some(liquidity) => liquidity,
+        // A missing side is unbounded, so only the given amount binds.
+        None => get_liquidity_from_amounts(
+            sqrt_price_x64,
+            get_sqrt_price_at_tick(tick_lower)?,
+            get_sqrt_price_at_tick(tick_upper)?,
+            amount_0.unwrap_or(u64::MAX),
+            amount_1.unwrap_or(u64::MAX),
+        )?,
+    };
+    let liquidity_delta = i128::try_from(liquidity)?;
+
+    let (deposit_0, deposit_1) = get_delta_amounts_signed(
+        tick_current,
+        sqrt_price_x64,
+        tick_lower,
+        tick_upper,
+        liquidity_delta,
+    )?;
+    let (withdraw_0, withdraw_1) = get_delta_amounts_signed(
+        tick_current,
+        sqrt_price_x64,
+        tick_lower,
+        tick_upper,
+        -liquidity_delta,
+    )?;
+
+    println!(
+        "Current Tick: {} (sqrt_price_x64 {})",
+        tick_current, sqrt_price_x64
+    );
+    println!(
+        "Range: ticks [{}, {}), prices [{}, {})",
+        tick_lower,
+        tick_upper,
+        tick_to_price(tick_lower, decimals_0, decimals_1)?,
+        tick_to_price(tick_upper, decimals_0, decimals_1)?
+    );
+    println!("Liquidity: {}", liquidity);
+    println!("Deposit: {} token 0, {} token 1", deposit_0, deposit_1);
+    println!("Withdraw: {} token 0, {} token 1", withdraw_0, withdraw_1);
+    Ok(())
+}
+
+fn run_cp_amm(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    command: &CpAmmCommand,
+) -> Result<Signature> {
+    let load_pool = |pool_id: &Pubkey| CpAmmPool::load(rpc_client, pool_id, &user.pubkey());
+
+    match *command {
+        CpAmmCommand::CreatePool {
+            mint_a,
+            mint_b,
+            amount_a,
+            amount_b,
+            amm_config_index,
+            trade_fee_rate,
+        } => {
+            let selector = match (amm_config_index, trade_fee_rate) {
+                (_, Some(trade_fee_rate)) => AmmConfigSelector::TradeFeeRate(trade_fee_rate),
+                (Some(index), None) => AmmConfigSelector::Index(index),
+                (None, None) => AmmConfigSelector::Index(0),
+            };
+            cp_amm_create_pool(
+                rpc_client, user, selector, &mint_a, &mint_b, amount_a, amount_b,
+            )
+        }
+        CpAmmCommand::ListConfigs => unreachable!("listing configs sends no transaction"),
+        CpAmmCommand::Deposit {
+            pool,
+            lp_amount,
+            token_0_amount,
+            token_1_amount,
+            ref slippage,
+        } => {
+            let mut pool = load_pool(&pool)?;
+            let slippage = Slippage::from(slippage);
+            match lp_amount {
+                Some(lp_amount) => {
+                    cp_amm_deposit_liquidity(rpc_client, user, &mut pool, lp_amount, slippage)
+                }
+                None => cp_amm_deposit_liquidity_by_token_amount(
+                    rpc_client,
+                    user,
+                    &mut pool,
+                    token_0_amount,
+                    token_1_amount,
+                    slippage,
+                ),
+            }
+        }
+        CpAmmCommand::Withdraw {
+            pool,
+            lp_amount,
+            ref slippage,
+        } => {
+            let mut pool = load_pool(&pool)?;
+            cp_amm_withdraw_liquidity(rpc_client, user, &mut pool, lp_amount, slippage.into())
+        }
+        CpAmmCommand::AtomicDepositWithdraw {
+            pool,
+            lp_amount,
+            ref slippage,
+        } => {
+            let mut pool = load_pool(&pool)?;
+            cp_amm_atomic_deposit_then_withdraw(
+                rpc_client,
+                user,
+                &mut pool,
+                lp_amount,
+                slippage.into(),
+            )
+        }
+        CpAmmCommand::AtomicWithdrawDeposit {
+            pool,
+            lp_amount,
+            ref slippage,
+        } => {
+            let mut pool = load_pool(&pool)?;
+            cp_amm_atomic_withdraw_then_deposit(
+                rpc_client,
+                user,
+                &mut pool,
+                lp_amount,
+                slippage.into(),
+            )
+        }
+        CpAmmCommand::SwapBaseInput {
+            pool,
+            input_mint,
+            amount_in,
+            ref slippage,
+        } => {
+            let mut pool = load_pool(&pool)?;
+            cp_amm_swap_base_input(
+                rpc_client,
+                user,
+                &mut pool,
+                &input_mint,
+                amount_in,
+                slippage.into(),
+            )
+        }
+        CpAmmCommand::SwapBaseOutput {
+            pool,
+            output_mint,
+            amount_out,
+            ref slippage,
+        } => {
+            let mut pool = load_pool(&pool)?;
+            cp_amm_swap_base_output(
+                rpc_client,
+                user,
+                &mut pool,
+                &output_mint,
+                amount_out,
+                slippage.into(),
+            )
+        }
+    }
+}
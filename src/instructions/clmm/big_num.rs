@@ -0,0 +1,65 @@
+#![allow(clippy::assign_op_pattern, clippy::manual_div_ceil)]
+
+use uint::construct_uint;
+
+construct_uint! {
+    pub struct U256(4);
+}
+
+construct_uint! {
+    pub struct U512(8);
+}
+
+impl From<U256> for U512 {
+    fn from(value: U256) -> Self {
+        let mut words = [0u64; 8];
+        words[..4].copy_from_slice(&value.0);
+        U512(words)
+    }
+}
+
+impl U256 {
+    fn from_u512(value: U512) -> Option<U256> {
+        if value.0[4..].iter().any(|word| *word != 0) {
+            return None;
+        }
+        let mut words = [0u64; 4];
+        words.copy_from_slice(&value.0[..4]);
+        Some(U256(words))
+    }
+
+    /// `self * num / denom` rounded down, without overflowing the product.
+    /// `None` if `denom` is zero or the result exceeds 256 bits.
+    pub fn mul_div_floor(self, num: U256, denom: U256) -> Option<U256> {
+        if denom.is_zero() {
+            return None;
+        }
+        U256::from_u512(U512::from(self) * U512::from(num) / U512::from(denom))
+    }
+
+    /// `self * num / denom` rounded up.
+    pub fn mul_div_ceil(self, num: U256, denom: U256) -> Option<U256> {
+        if denom.is_zero() {
+            return None;
+        }
+        let product = U512::from(self) * U512::from(num);
+        let denom = U512::from(denom);
+        let (quotient, remainder) = product.div_mod(denom);
+        let quotient = if remainder.is_zero() {
+            quotient
+        } else {
+            quotient + 1
+        };
+        U256::from_u512(quotient)
+    }
+
+    /// `self / other` rounded up.
+    pub fn div_rounding_up(self, other: U256) -> U256 {
+        let (quotient, remainder) = self.div_mod(other);
+        if remainder.is_zero() {
+            quotient
+        } else {
+            quotient + 1
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use super::clmm_decrease_liquidity::decrease_liquidity_instructions;
+use super::clmm_pool::ClmmPool;
+use super::clmm_position::ClmmPosition;
+use crate::utils::slippage::Slippage;
+
+/// Closes `position` and returns the lamports its accounts held. With
+/// `decrease_first`, any remaining liquidity, fees and rewards are withdrawn
+/// in the same transaction; otherwise the position must already be empty.
+pub fn clmm_close_position(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    position: &ClmmPosition,
+    decrease_first: bool,
+    slippage: Slippage,
+) -> Result<(Signature, u64)> {
+    pool.ensure_owner(&user.pubkey())?;
+    position.ensure_pool(&pool.pool_id)?;
+
+    println!("Closing position {}", position.nft_mint);
+
+    let mut instructions = Vec::new();
+    if decrease_first {
+        instructions = decrease_liquidity_instructions(rpc_client, pool, position, None, slippage)?;
+    } else if position.state.liquidity != 0 {
+        return Err(format_err!(
+            "Position {} still has {} liquidity; remove it first or collect in the same transaction",
+            position.nft_mint,
+            position.state.liquidity
+        ));
+    } else if position.state.has_amounts_owed() {
+        return Err(format_err!(
+            "Position {} still has fees or rewards owed; collect them first or in the same transaction",
+            position.nft_mint
+        ));
+    }
+    instructions.push(position.close_position_instruction(&user.pubkey())?);
+
+    // SPL Token mints cannot be closed, so only Token-2022 mints return rent.
+    let mut closed_accounts = vec![position.personal_position, position.nft_account];
+    if position.nft_token_program == spl_token_2022::id() {
+        closed_accounts.push(position.nft_mint);
+    }
+    let reclaimed_lamports = rpc_client
+        .get_multiple_accounts(&closed_accounts)?
+        .iter()
+        .flatten()
+        .map(|account| account.lamports)
+        .sum();
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending close_position transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok((signature, reclaimed_lamports))
+}
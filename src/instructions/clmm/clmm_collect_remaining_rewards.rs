@@ -0,0 +1,91 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Account as TokenAccount;
+
+use super::clmm_pool::ClmmPool;
+use super::clmm_reward::fetch_cluster_time;
+use crate::utils::token_utils::fetch_mint_token_program;
+
+/// Withdraws what an ended reward stream's vault holds beyond what
+/// positions are still owed, as the stream's authority.
+pub fn clmm_collect_remaining_rewards(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    reward_mint: &Pubkey,
+) -> Result<Signature> {
+    pool.ensure_owner(&user.pubkey())?;
+
+    println!("Fetching live pool data...");
+    pool.refresh(rpc_client)?;
+    let now = fetch_cluster_time(rpc_client)?;
+
+    let reward_index = pool.reward_index(reward_mint)?;
+    let reward_info = pool.state.reward_infos[usize::from(reward_index)];
+    if reward_info.authority != user.pubkey() {
+        return Err(format_err!(
+            "Reward {} is managed by {}, not {}",
+            reward_mint,
+            reward_info.authority,
+            user.pubkey()
+        ));
+    }
+    if now <= reward_info.end_time {
+        return Err(format_err!(
+            "Reward {} runs until {}, the cluster time is {}",
+            reward_mint,
+            reward_info.end_time,
+            now
+        ));
+    }
+
+    let vault_account = rpc_client.get_account(&reward_info.token_vault)?;
+    let vault_balance = StateWithExtensions::<TokenAccount>::unpack(&vault_account.data)?
+        .base
+        .amount;
+    let still_owed = reward_info
+        .reward_total_emissioned
+        .saturating_sub(reward_info.reward_claimed);
+    println!(
+        "Reward Vault: {} holds {}, {} still owed to positions",
+        reward_info.token_vault, vault_balance, still_owed
+    );
+
+    let token_program = fetch_mint_token_program(rpc_client, reward_mint)?;
+    let reward = pool.reward_accounts_for(reward_mint, &token_program);
+    println!(
+        "Collecting about {} into {}",
+        vault_balance.saturating_sub(still_owed),
+        reward.recipient
+    );
+
+    let instructions = vec![
+        create_associated_token_account_idempotent(
+            &pool.owner,
+            &pool.owner,
+            reward_mint,
+            &token_program,
+        ),
+        pool.collect_remaining_rewards_instruction(reward_index, &reward)?,
+    ];
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending collect_remaining_rewards transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok(signature)
+}
@@ -0,0 +1,88 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use super::clmm_pool::ClmmPool;
+use super::clmm_position::fetch_owned_positions;
+use crate::utils::transaction_utils::pack_instructions;
+
+/// Collects the fees and rewards of every position the user holds in the
+/// pool, without removing liquidity. Returns one signature per transaction
+/// sent.
+pub fn clmm_collect_rewards(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+) -> Result<Vec<Signature>> {
+    pool.ensure_owner(&user.pubkey())?;
+
+    println!("Fetching live pool data...");
+    pool.refresh(rpc_client)?;
+
+    println!("Scanning {} for positions...", user.pubkey());
+    let positions: Vec<_> = fetch_owned_positions(rpc_client, &user.pubkey())?
+        .into_iter()
+        .filter(|position| position.state.pool_id == pool.pool_id)
+        .filter(|position| position.state.liquidity != 0 || position.state.has_amounts_owed())
+        .collect();
+    if positions.is_empty() {
+        return Err(format_err!(
+            "{} has no positions in pool {} with anything to collect",
+            user.pubkey(),
+            pool.pool_id
+        ));
+    }
+
+    let reward_accounts = pool.fetch_reward_accounts(rpc_client)?;
+    if reward_accounts.is_empty() {
+        println!("Pool has no reward streams, collecting fees only");
+    }
+    let mut instructions: Vec<Instruction> = reward_accounts
+        .iter()
+        .map(|reward| {
+            println!(
+                "Collecting reward {} into {}",
+                reward.reward_mint, reward.recipient
+            );
+            create_associated_token_account_idempotent(
+                &pool.owner,
+                &pool.owner,
+                &reward.reward_mint,
+                &reward.token_program,
+            )
+        })
+        .collect();
+    for position in &positions {
+        println!("Harvesting position {}", position.nft_mint);
+        instructions.push(pool.decrease_liquidity_instruction(
+            position,
+            0,
+            0,
+            0,
+            &reward_accounts,
+        )?);
+    }
+
+    // Each harvest carries its own position accounts plus every reward
+    // stream's, so how many fit depends on the pool.
+    let mut signatures = Vec::new();
+    for batch in pack_instructions(&user.pubkey(), instructions)? {
+        let latest_blockhash = rpc_client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &batch,
+            Some(&user.pubkey()),
+            &[user],
+            latest_blockhash,
+        );
+
+        println!("Sending collect transaction...");
+        signatures.push(rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?);
+    }
+
+    Ok(signatures)
+}
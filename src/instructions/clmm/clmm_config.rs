@@ -0,0 +1,38 @@
+use anyhow::{Result, format_err};
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+pub const FEE_RATE_DENOMINATOR_VALUE: u32 = 1_000_000;
+
+const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct AmmConfig {
+    pub bump: u8,
+    pub index: u16,
+    pub owner: Pubkey,
+    pub protocol_fee_rate: u32,
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+    pub padding_u32: u32,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 3],
+}
+
+impl AmmConfig {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != AMM_CONFIG_DISCRIMINATOR {
+            return Err(format_err!("Account is not a CLMM AmmConfig"));
+        }
+        Ok(AmmConfig::deserialize(&mut &data[8..])?)
+    }
+}
+
+pub fn fetch_amm_config(rpc_client: &RpcClient, amm_config: &Pubkey) -> Result<AmmConfig> {
+    let data = rpc_client.get_account_data(amm_config)?;
+    AmmConfig::from_account_data(&data)
+}
@@ -0,0 +1,125 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use super::clmm_pool::ClmmPool;
+use super::clmm_position::ClmmPosition;
+use super::clmm_quote::quote_decrease_liquidity;
+use crate::utils::slippage::Slippage;
+
+/// Instructions removing `liquidity` from `position`, or all of it when
+/// `None`, and collecting its fees and rewards. Reward accounts the owner
+/// does not have yet are created first.
+pub fn decrease_liquidity_instructions(
+    rpc_client: &RpcClient,
+    pool: &mut ClmmPool,
+    position: &ClmmPosition,
+    liquidity: Option<u128>,
+    slippage: Slippage,
+) -> Result<Vec<Instruction>> {
+    position.ensure_pool(&pool.pool_id)?;
+    slippage.validate()?;
+
+    let liquidity = liquidity.unwrap_or(position.state.liquidity);
+    if liquidity > position.state.liquidity {
+        return Err(format_err!(
+            "Position {} only has {} liquidity, cannot remove {}",
+            position.nft_mint,
+            position.state.liquidity,
+            liquidity
+        ));
+    }
+
+    println!("Fetching live pool data...");
+    let transfer_fees = pool.refresh(rpc_client)?;
+
+    let quote = quote_decrease_liquidity(
+        &pool.state,
+        position.tick_range(),
+        liquidity,
+        &transfer_fees,
+    )?;
+    let (amount_0_to_receive, amount_1_to_receive) = quote.withdraw_amounts();
+
+    let amount_0_min = slippage.min_amount(amount_0_to_receive)?;
+    let amount_1_min = slippage.min_amount(amount_1_to_receive)?;
+
+    println!(
+        "Liquidity: {} - {}",
+        position.state.liquidity, quote.liquidity
+    );
+    println!(
+        "Expected Token 0: {} (transfer fee {}), Min Accepted: {}",
+        amount_0_to_receive, quote.transfer_fee_0, amount_0_min
+    );
+    println!(
+        "Expected Token 1: {} (transfer fee {}), Min Accepted: {}",
+        amount_1_to_receive, quote.transfer_fee_1, amount_1_min
+    );
+
+    let reward_accounts = pool.fetch_reward_accounts(rpc_client)?;
+    let mut instructions: Vec<Instruction> = reward_accounts
+        .iter()
+        .map(|reward| {
+            println!(
+                "Collecting reward {} into {}",
+                reward.reward_mint, reward.recipient
+            );
+            create_associated_token_account_idempotent(
+                &pool.owner,
+                &pool.owner,
+                &reward.reward_mint,
+                &reward.token_program,
+            )
+        })
+        .collect();
+    instructions.push(pool.decrease_liquidity_instruction(
+        position,
+        quote.liquidity,
+        amount_0_min,
+        amount_1_min,
+        &reward_accounts,
+    )?);
+
+    Ok(instructions)
+}
+
+/// Removes `liquidity` from `position`, or all of it when `None`, collecting
+/// the fees and rewards it has earned.
+pub fn clmm_decrease_liquidity(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    position: &ClmmPosition,
+    liquidity: Option<u128>,
+    slippage: Slippage,
+) -> Result<Signature> {
+    pool.ensure_owner(&user.pubkey())?;
+
+    println!(
+        "Removing {} liquidity from position {}",
+        liquidity.map_or("all".to_string(), |liquidity| liquidity.to_string()),
+        position.nft_mint
+    );
+
+    let instructions =
+        decrease_liquidity_instructions(rpc_client, pool, position, liquidity, slippage)?;
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending decrease_liquidity transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok(signature)
+}
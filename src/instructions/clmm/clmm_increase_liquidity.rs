@@ -0,0 +1,82 @@
+use anyhow::Result;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use super::clmm_pool::ClmmPool;
+use super::clmm_position::ClmmPosition;
+use super::clmm_quote::quote_position_from_amounts;
+use crate::utils::slippage::Slippage;
+
+/// Adds the most liquidity affordable with at most `maximum_amount_0` and
+/// `maximum_amount_1` leaving the wallet, transfer fees included. Either side
+/// may be `None`, in which case it is sized from the other. Returns the
+/// liquidity added.
+pub fn clmm_increase_liquidity(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    position: &ClmmPosition,
+    maximum_amount_0: Option<u64>,
+    maximum_amount_1: Option<u64>,
+    slippage: Slippage,
+) -> Result<(Signature, u128)> {
+    pool.ensure_owner(&user.pubkey())?;
+    position.ensure_pool(&pool.pool_id)?;
+    slippage.validate()?;
+
+    println!(
+        "Adding up to {:?} token 0 and {:?} token 1 to position {}",
+        maximum_amount_0, maximum_amount_1, position.nft_mint
+    );
+
+    println!("Fetching live pool data...");
+    let transfer_fees = pool.refresh(rpc_client)?;
+
+    let quote = quote_position_from_amounts(
+        &pool.state,
+        position.tick_range(),
+        maximum_amount_0
+            .map(|maximum| slippage.quote_within(maximum))
+            .transpose()?,
+        maximum_amount_1
+            .map(|maximum| slippage.quote_within(maximum))
+            .transpose()?,
+        &transfer_fees,
+    )?;
+    let (amount_0_to_deposit, amount_1_to_deposit) = quote.deposit_amounts()?;
+
+    let amount_0_max = slippage.max_amount_capped(amount_0_to_deposit, maximum_amount_0)?;
+    let amount_1_max = slippage.max_amount_capped(amount_1_to_deposit, maximum_amount_1)?;
+
+    println!(
+        "Liquidity: {} + {}",
+        position.state.liquidity, quote.liquidity
+    );
+    println!(
+        "Required Token 0: {} (transfer fee {}), Max Allowed: {}",
+        amount_0_to_deposit, quote.transfer_fee_0, amount_0_max
+    );
+    println!(
+        "Required Token 1: {} (transfer fee {}), Max Allowed: {}",
+        amount_1_to_deposit, quote.transfer_fee_1, amount_1_max
+    );
+
+    let instruction =
+        pool.increase_liquidity_instruction(position, quote.liquidity, amount_0_max, amount_1_max)?;
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending increase_liquidity transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok((signature, quote.liquidity))
+}
@@ -0,0 +1,76 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use super::clmm_pool::ClmmPool;
+use super::clmm_reward::{RewardFunder, RewardSchedule, fetch_cluster_time};
+
+/// Opens a reward stream in `reward_mint` on the pool, paying the whole
+/// schedule into its vault from the user's token account up front.
+pub fn clmm_initialize_reward(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    reward_mint: &Pubkey,
+    schedule: RewardSchedule,
+) -> Result<Signature> {
+    pool.ensure_owner(&user.pubkey())?;
+
+    println!("Fetching live pool data...");
+    pool.refresh(rpc_client)?;
+    let now = fetch_cluster_time(rpc_client)?;
+    schedule.validate(now)?;
+
+    let reward_infos = &pool.state.reward_infos;
+    if reward_infos
+        .iter()
+        .any(|reward_info| reward_info.initialized() && reward_info.token_mint == *reward_mint)
+    {
+        return Err(format_err!(
+            "Pool {} already has a reward stream for {}",
+            pool.pool_id,
+            reward_mint
+        ));
+    }
+    let reward_index = reward_infos
+        .iter()
+        .position(|reward_info| !reward_info.initialized())
+        .ok_or_else(|| format_err!("All reward slots of pool {} are in use", pool.pool_id))?;
+
+    let funder = RewardFunder::load(rpc_client, &user.pubkey(), reward_mint)?;
+    let total_amount = schedule.total_amount()?;
+    let required = funder.ensure_covers(total_amount)?;
+
+    println!(
+        "Reward {}: {} from {} to {}, {} per second (Q64.64)",
+        reward_index,
+        reward_mint,
+        schedule.open_time,
+        schedule.end_time,
+        schedule.emissions_per_second_x64
+    );
+    println!(
+        "Funding: {} (with transfer fee {}) from {}, balance {}",
+        total_amount, required, funder.token_account, funder.balance
+    );
+
+    let reward = pool.reward_accounts_for(reward_mint, &funder.token_program);
+    let instruction = pool.initialize_reward_instruction(&reward, &schedule)?;
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending initialize_reward transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok(signature)
+}
@@ -0,0 +1,473 @@
+use anyhow::{Result, format_err};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::clmm_close_position::clmm_close_position;
+use super::clmm_open_position::clmm_open_position;
+use super::clmm_pool::ClmmPool;
+use super::clmm_position::ClmmPosition;
+use super::tick_math::{TickRange, ceil_tick_to_spacing, floor_tick_to_spacing};
+use crate::utils::slippage::Slippage;
+
+/// Which token an order sells. Token 0 is sold as the price rises through
+/// a range above it, token 1 as the price falls through a range below it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitOrderSide {
+    SellToken0,
+    SellToken1,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitOrderStatus {
+    /// The price has not reached the range.
+    Open,
+    /// The price is inside the range, so part of the order has converted.
+    PartiallyCrossed,
+    /// The price crossed the whole range and the position was closed.
+    Filled,
+    /// The position was closed before the order filled.
+    Cancelled,
+}
+
+impl LimitOrderStatus {
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Open | Self::PartiallyCrossed)
+    }
+}
+
+/// A limit order placed as a single-sided position one tick spacing wide.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LimitOrder {
+    #[serde(with = "pubkey_string")]
+    pub position_nft_mint: Pubkey,
+    #[serde(with = "pubkey_string")]
+    pub pool_id: Pubkey,
+    pub side: LimitOrderSide,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// Most of the sold token deposited, transfer fees included.
+    pub amount: u64,
+    pub liquidity: u128,
+    pub status: LimitOrderStatus,
+    /// Unix time the order was placed.
+    pub created_at: u64,
+    pub open_signature: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_signature: Option<String>,
+}
+
+impl LimitOrder {
+    /// Where the price at `tick_current` leaves the order: open, partially
+    /// crossed, or crossed in full, which is reported as `Filled` even
+    /// though the position still has to be closed to realise it.
+    pub fn crossing_status(&self, tick_current: i32) -> LimitOrderStatus {
+        let (reached, crossed) = match self.side {
+            LimitOrderSide::SellToken0 => (
+                tick_current >= self.tick_lower,
+                tick_current >= self.tick_upper,
+            ),
+            LimitOrderSide::SellToken1 => (
+                tick_current < self.tick_upper,
+                tick_current < self.tick_lower,
+            ),
+        };
+        if crossed {
+            LimitOrderStatus::Filled
+        } else if reached {
+            LimitOrderStatus::PartiallyCrossed
+        } else {
+            LimitOrderStatus::Open
+        }
+    }
+}
+
+/// Orders kept in a local JSON file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LimitOrderBook {
+    pub orders: Vec<LimitOrder>,
+}
+
+impl LimitOrderBook {
+    /// Reads the book at `path`, or starts an empty one if there is none.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the book to a temporary file beside `path` and renames it over
+    /// the book, so a concurrent reader never sees a partial file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| format_err!("{} is not a file path", path.display()))?;
+        let mut temp_name = file_name.to_os_string();
+        temp_name.push(".tmp");
+        let temp_path = path.with_file_name(temp_name);
+        fs::write(&temp_path, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn find_mut(&mut self, position_nft_mint: &Pubkey) -> Result<&mut LimitOrder> {
+        self.orders
+            .iter_mut()
+            .find(|order| order.position_nft_mint == *position_nft_mint)
+            .ok_or_else(|| format_err!("No limit order for position {}", position_nft_mint))
+    }
+}
+
+/// The one-spacing range nearest the current price that holds only the sold
+/// token: the first one entirely above `tick_current` when selling token 0,
+/// the first one at or below it when selling token 1.
+pub fn limit_order_tick_range(
+    side: LimitOrderSide,
+    tick_current: i32,
+    tick_spacing: u16,
+) -> TickRange {
+    let spacing = i32::from(tick_spacing);
+    match side {
+        LimitOrderSide::SellToken0 => {
+            let tick_lower = ceil_tick_to_spacing(tick_current + 1, tick_spacing);
+            TickRange {
+                tick_lower,
+                tick_upper: tick_lower + spacing,
+            }
+        }
+        LimitOrderSide::SellToken1 => {
+            let tick_upper = floor_tick_to_spacing(tick_current, tick_spacing);
+            TickRange {
+                tick_lower: tick_upper - spacing,
+                tick_upper,
+            }
+        }
+    }
+}
+
+/// Sells `amount` of `sell_mint` by opening a single-sided position just
+/// beyond the current price.
+pub fn clmm_place_limit_order(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    sell_mint: &Pubkey,
+    amount: u64,
+    slippage: Slippage,
+) -> Result<LimitOrder> {
+    let side = if pool.is_token_0(sell_mint)? {
+        LimitOrderSide::SellToken0
+    } else {
+        LimitOrderSide::SellToken1
+    };
+    pool.refresh(rpc_client)?;
+    let tick_range = limit_order_tick_range(side, pool.state.tick_current, pool.state.tick_spacing);
+    println!(
+        "Placing limit order: sell {} of {} over ticks [{}, {}), current tick {}",
+        amount, sell_mint, tick_range.tick_lower, tick_range.tick_upper, pool.state.tick_current
+    );
+
+    let (amount_0, amount_1) = match side {
+        LimitOrderSide::SellToken0 => (Some(amount), None),
+        LimitOrderSide::SellToken1 => (None, Some(amount)),
+    };
+    let (signature, opened) = clmm_open_position(
+        rpc_client, user, pool, tick_range, amount_0, amount_1, slippage,
+    )?;
+
+    Ok(LimitOrder {
+        position_nft_mint: opened.position_nft_mint,
+        pool_id: pool.pool_id,
+        side,
+        tick_lower: tick_range.tick_lower,
+        tick_upper: tick_range.tick_upper,
+        amount,
+        liquidity: opened.liquidity,
+        status: LimitOrderStatus::Open,
+        created_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        open_signature: signature.to_string(),
+        close_signature: None,
+    })
+}
+
+/// Closes the order's position, withdrawing whatever mix of tokens it holds
+/// along with its fees, and marks the order with `status`.
+pub fn clmm_close_limit_order(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    order: &mut LimitOrder,
+    status: LimitOrderStatus,
+    slippage: Slippage,
+) -> Result<Signature> {
+    if !order.status.is_active() {
+        return Err(format_err!(
+            "Limit order {} is already {:?}",
+            order.position_nft_mint,
+            order.status
+        ));
+    }
+    let position = ClmmPosition::load(rpc_client, &order.position_nft_mint, &user.pubkey())?;
+    let mut pool = ClmmPool::load(rpc_client, &order.pool_id, &user.pubkey())?;
+    let (signature, _) =
+        clmm_close_position(rpc_client, user, &mut pool, &position, true, slippage)?;
+
+    order.status = status;
+    order.close_signature = Some(signature.to_string());
+    Ok(signature)
+}
+
+/// Outcome of one pass over the book.
+#[derive(Debug, Clone, Default)]
+pub struct SettlePass {
+    /// Signatures of the orders filled.
+    pub signatures: Vec<Signature>,
+    /// Orders still active once the pass finished.
+    pub active_orders: usize,
+}
+
+/// Brings the active orders in the book at `book_path` up to date with
+/// their pools' prices, closing any the price has fully crossed. The book is
+/// re-read for every pass, so orders placed or cancelled in the meantime are
+/// picked up, and an order that fails is logged and left for the next pass.
+pub fn clmm_settle_limit_orders(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    book_path: &Path,
+    slippage: Slippage,
+) -> Result<SettlePass> {
+    let book = LimitOrderBook::load(book_path)?;
+    let mut signatures = Vec::new();
+    for order in book.orders {
+        if !order.status.is_active() {
+            continue;
+        }
+        let position_nft_mint = order.position_nft_mint;
+        match settle_limit_order(rpc_client, user, order, book_path, slippage) {
+            Ok(signature) => signatures.extend(signature),
+            Err(err) => println!(
+                "Failed to settle limit order {}: {}",
+                position_nft_mint, err
+            ),
+        }
+    }
+
+    let active_orders = LimitOrderBook::load(book_path)?
+        .orders
+        .iter()
+        .filter(|order| order.status.is_active())
+        .count();
+    Ok(SettlePass {
+        signatures,
+        active_orders,
+    })
+}
+
+/// Moves `order` to the status its pool's price puts it in, closing it once
+/// filled, and saves the change. The stored status is checked against the
+/// one `order` was read with before acting and again before saving, so an
+/// order cancelled meanwhile is not overwritten.
+fn settle_limit_order(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    mut order: LimitOrder,
+    book_path: &Path,
+    slippage: Slippage,
+) -> Result<Option<Signature>> {
+    let pool = ClmmPool::load(rpc_client, &order.pool_id, &user.pubkey())?;
+    let status = order.crossing_status(pool.state.tick_current);
+    let read_status = order.status;
+    if status == read_status {
+        return Ok(None);
+    }
+    println!(
+        "Limit order {} is now {:?} at tick {}",
+        order.position_nft_mint, status, pool.state.tick_current
+    );
+
+    let changed = |stored: &LimitOrder| {
+        format_err!(
+            "Limit order {} changed to {:?} while being settled",
+            stored.position_nft_mint,
+            stored.status
+        )
+    };
+    let signature = if status == LimitOrderStatus::Filled {
+        let mut book = LimitOrderBook::load(book_path)?;
+        let stored = book.find_mut(&order.position_nft_mint)?;
+        if stored.status != read_status {
+            return Err(changed(stored));
+        }
+        Some(clmm_close_limit_order(
+            rpc_client,
+            user,
+            &mut order,
+            LimitOrderStatus::Filled,
+            slippage,
+        )?)
+    } else {
+        order.status = status;
+        None
+    };
+
+    let mut book = LimitOrderBook::load(book_path)?;
+    let stored = book.find_mut(&order.position_nft_mint)?;
+    // Once the position is closed the fill stands, whatever was stored.
+    if signature.is_none() && stored.status != read_status {
+        return Err(changed(stored));
+    }
+    *stored = order;
+    book.save(book_path)?;
+    Ok(signature)
+}
+
+/// Stores pubkeys as base58 strings, so the book stays readable.
+mod pubkey_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: LimitOrderSide, tick_lower: i32, tick_upper: i32) -> LimitOrder {
+        LimitOrder {
+            position_nft_mint: Pubkey::new_unique(),
+            pool_id: Pubkey::new_unique(),
+            side,
+            tick_lower,
+            tick_upper,
+            amount: 1_000_000,
+            liquidity: 1 << 40,
+            status: LimitOrderStatus::Open,
+            created_at: 0,
+            open_signature: Signature::default().to_string(),
+            close_signature: None,
+        }
+    }
+
+    fn range(tick_lower: i32, tick_upper: i32) -> TickRange {
+        TickRange {
+            tick_lower,
+            tick_upper,
+        }
+    }
+
+    #[test]
+    fn sell_token_0_range_is_strictly_above_the_price() {
+        let side = LimitOrderSide::SellToken0;
+        assert_eq!(limit_order_tick_range(side, 100, 60), range(120, 180));
+        assert_eq!(limit_order_tick_range(side, 119, 60), range(120, 180));
+        // On a boundary the pool counts the range from here as in range.
+        assert_eq!(limit_order_tick_range(side, 120, 60), range(180, 240));
+        assert_eq!(limit_order_tick_range(side, -61, 60), range(-60, 0));
+        assert_eq!(limit_order_tick_range(side, -60, 60), range(0, 60));
+        assert_eq!(limit_order_tick_range(side, -1, 60), range(0, 60));
+    }
+
+    #[test]
+    fn sell_token_1_range_is_at_or_below_the_price() {
+        let side = LimitOrderSide::SellToken1;
+        assert_eq!(limit_order_tick_range(side, 100, 60), range(0, 60));
+        // On a boundary the range ending there is already out of range.
+        assert_eq!(limit_order_tick_range(side, 120, 60), range(60, 120));
+        assert_eq!(limit_order_tick_range(side, 0, 60), range(-60, 0));
+        assert_eq!(limit_order_tick_range(side, -1, 60), range(-120, -60));
+        assert_eq!(limit_order_tick_range(side, -60, 60), range(-120, -60));
+        assert_eq!(limit_order_tick_range(side, -61, 60), range(-180, -120));
+    }
+
+    #[test]
+    fn sell_token_0_crosses_as_the_price_rises() {
+        let order = order(LimitOrderSide::SellToken0, 120, 180);
+        assert_eq!(order.crossing_status(-500), LimitOrderStatus::Open);
+        assert_eq!(order.crossing_status(119), LimitOrderStatus::Open);
+        assert_eq!(
+            order.crossing_status(120),
+            LimitOrderStatus::PartiallyCrossed
+        );
+        assert_eq!(
+            order.crossing_status(179),
+            LimitOrderStatus::PartiallyCrossed
+        );
+        assert_eq!(order.crossing_status(180), LimitOrderStatus::Filled);
+        assert_eq!(order.crossing_status(10_000), LimitOrderStatus::Filled);
+    }
+
+    #[test]
+    fn sell_token_1_crosses_as_the_price_falls() {
+        let order = order(LimitOrderSide::SellToken1, -120, -60);
+        assert_eq!(order.crossing_status(500), LimitOrderStatus::Open);
+        assert_eq!(order.crossing_status(-60), LimitOrderStatus::Open);
+        assert_eq!(
+            order.crossing_status(-61),
+            LimitOrderStatus::PartiallyCrossed
+        );
+        assert_eq!(
+            order.crossing_status(-120),
+            LimitOrderStatus::PartiallyCrossed
+        );
+        assert_eq!(order.crossing_status(-121), LimitOrderStatus::Filled);
+        assert_eq!(order.crossing_status(-10_000), LimitOrderStatus::Filled);
+    }
+
+    #[test]
+    fn placed_ranges_start_open() {
+        for tick_current in [-181, -120, -61, -60, -1, 0, 1, 59, 60, 61, 180] {
+            for side in [LimitOrderSide::SellToken0, LimitOrderSide::SellToken1] {
+                let tick_range = limit_order_tick_range(side, tick_current, 60);
+                let order = order(side, tick_range.tick_lower, tick_range.tick_upper);
+                assert_eq!(
+                    order.crossing_status(tick_current),
+                    LimitOrderStatus::Open,
+                    "{:?} at tick {}",
+                    side,
+                    tick_current
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn save_replaces_the_book_without_leaving_a_temp_file() {
+        let dir = std::env::temp_dir().join(format!("limit-order-book-{}", Pubkey::new_unique()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("orders.json");
+
+        let mut book = LimitOrderBook::default();
+        book.orders
+            .push(order(LimitOrderSide::SellToken0, 120, 180));
+        book.save(&path).unwrap();
+        book.orders
+            .push(order(LimitOrderSide::SellToken1, -120, -60));
+        book.save(&path).unwrap();
+
+        let loaded = LimitOrderBook::load(&path).unwrap();
+        assert_eq!(loaded.orders.len(), 2);
+        assert_eq!(
+            loaded.orders[1].position_nft_mint,
+            book.orders[1].position_nft_mint
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,51 @@
+use anyhow::{Result, format_err};
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+pub const OBSERVATION_NUM: usize = 100;
+
+const OBSERVATION_STATE_DISCRIMINATOR: [u8; 8] = [122, 174, 197, 53, 129, 9, 165, 132];
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone, Copy, Default)]
+pub struct Observation {
+    pub block_timestamp: u32,
+    pub tick_cumulative: i64,
+    pub padding: [u64; 4],
+}
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct ObservationState {
+    pub initialized: bool,
+    pub recent_epoch: u64,
+    pub observation_index: u16,
+    pub pool_id: Pubkey,
+    pub observations: [Observation; OBSERVATION_NUM],
+    pub padding: [u64; 4],
+}
+
+impl ObservationState {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != OBSERVATION_STATE_DISCRIMINATOR {
+            return Err(format_err!("Account is not a CLMM ObservationState"));
+        }
+        Ok(ObservationState::deserialize(&mut &data[8..])?)
+    }
+
+    /// The most recently written observation.
+    pub fn latest(&self) -> &Observation {
+        &self.observations[self.observation_index as usize % OBSERVATION_NUM]
+    }
+}
+
+pub fn fetch_observation_state(
+    rpc_client: &RpcClient,
+    observation_state: &Pubkey,
+) -> Result<ObservationState> {
+    let data = rpc_client.get_account_data(observation_state)?;
+    ObservationState::from_account_data(&data)
+}
@@ -0,0 +1,164 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+
+use super::clmm_pool::ClmmPool;
+use super::clmm_position::personal_position_address;
+use super::clmm_quote::quote_position_from_amounts;
+use super::clmm_tick_array::tick_array_address;
+use super::tick_math::{MAX_TICK, MIN_TICK, TickRange};
+use crate::utils::slippage::Slippage;
+
+/// A freshly opened position and the NFT that controls it.
+#[derive(Debug, Clone, Copy)]
+pub struct OpenedPosition {
+    pub position_nft_mint: Pubkey,
+    pub personal_position: Pubkey,
+    pub tick_range: TickRange,
+    pub liquidity: u128,
+}
+
+fn validate_tick_range(tick_range: TickRange, tick_spacing: u16) -> Result<()> {
+    let TickRange {
+        tick_lower,
+        tick_upper,
+    } = tick_range;
+    if tick_lower >= tick_upper {
+        return Err(format_err!(
+            "Lower tick {} must be below upper tick {}",
+            tick_lower,
+            tick_upper
+        ));
+    }
+    if tick_lower < MIN_TICK || tick_upper > MAX_TICK {
+        return Err(format_err!(
+            "Ticks must lie within [{}, {}]",
+            MIN_TICK,
+            MAX_TICK
+        ));
+    }
+    let spacing = tick_spacing as i32;
+    if tick_lower % spacing != 0 || tick_upper % spacing != 0 {
+        return Err(format_err!(
+            "Ticks {} and {} are not multiples of the pool's tick spacing {}",
+            tick_lower,
+            tick_upper,
+            tick_spacing
+        ));
+    }
+    Ok(())
+}
+
+/// Opens a position over `tick_range` with the most liquidity affordable
+/// with at most `maximum_amount_0` and `maximum_amount_1` leaving the wallet,
+/// transfer fees included. Either side may be `None`, in which case it is
+/// sized from the other.
+pub fn clmm_open_position(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    tick_range: TickRange,
+    maximum_amount_0: Option<u64>,
+    maximum_amount_1: Option<u64>,
+    slippage: Slippage,
+) -> Result<(Signature, OpenedPosition)> {
+    pool.ensure_owner(&user.pubkey())?;
+    slippage.validate()?;
+
+    println!(
+        "Opening position over ticks [{}, {}) in pool {}",
+        tick_range.tick_lower, tick_range.tick_upper, pool.pool_id
+    );
+
+    println!("Fetching live pool data...");
+    let transfer_fees = pool.refresh(rpc_client)?;
+    validate_tick_range(tick_range, pool.state.tick_spacing)?;
+
+    let quote = quote_position_from_amounts(
+        &pool.state,
+        tick_range,
+        maximum_amount_0
+            .map(|maximum| slippage.quote_within(maximum))
+            .transpose()?,
+        maximum_amount_1
+            .map(|maximum| slippage.quote_within(maximum))
+            .transpose()?,
+        &transfer_fees,
+    )?;
+    let (amount_0_to_deposit, amount_1_to_deposit) = quote.deposit_amounts()?;
+
+    let amount_0_max = slippage.max_amount_capped(amount_0_to_deposit, maximum_amount_0)?;
+    let amount_1_max = slippage.max_amount_capped(amount_1_to_deposit, maximum_amount_1)?;
+
+    println!("Liquidity: {}", quote.liquidity);
+    println!(
+        "Required Token 0: {} (transfer fee {}), Max Allowed: {}",
+        amount_0_to_deposit, quote.transfer_fee_0, amount_0_max
+    );
+    println!(
+        "Required Token 1: {} (transfer fee {}), Max Allowed: {}",
+        amount_1_to_deposit, quote.transfer_fee_1, amount_1_max
+    );
+
+    let (tick_array_lower_start_index, tick_array_upper_start_index) =
+        pool.tick_array_start_indexes(tick_range);
+    let tick_arrays = rpc_client.get_multiple_accounts(&[
+        tick_array_address(
+            &pool.program_id,
+            &pool.pool_id,
+            tick_array_lower_start_index,
+        ),
+        tick_array_address(
+            &pool.program_id,
+            &pool.pool_id,
+            tick_array_upper_start_index,
+        ),
+    ])?;
+    for (start_index, tick_array) in [tick_array_lower_start_index, tick_array_upper_start_index]
+        .into_iter()
+        .zip(tick_arrays)
+    {
+        if tick_array.is_none() {
+            println!("Tick array starting at {} will be initialised", start_index);
+        }
+    }
+
+    let position_nft_mint = Keypair::new();
+    println!("Position NFT Mint: {}", position_nft_mint.pubkey());
+
+    let instruction = pool.open_position_instruction(
+        &position_nft_mint.pubkey(),
+        quote.tick_range,
+        quote.liquidity,
+        amount_0_max,
+        amount_1_max,
+    )?;
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&user.pubkey()),
+        &[user, &position_nft_mint],
+        latest_blockhash,
+    );
+
+    println!("Sending open_position transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok((
+        signature,
+        OpenedPosition {
+            position_nft_mint: position_nft_mint.pubkey(),
+            personal_position: personal_position_address(
+                &pool.program_id,
+                &position_nft_mint.pubkey(),
+            ),
+            tick_range: quote.tick_range,
+            liquidity: quote.liquidity,
+        },
+    ))
+}
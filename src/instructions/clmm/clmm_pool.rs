@@ -0,0 +1,789 @@
+use anyhow::{Result, format_err};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+use solana_system_interface::program as system_program;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use std::str::FromStr;
+
+use super::clmm_position::{ClmmPosition, personal_position_address, protocol_position_address};
+use super::clmm_price::sqrt_price_x64_to_price;
+use super::clmm_reward::{RewardSchedule, operation_state_address, reward_vault_address};
+use super::clmm_tick_array::{
+    get_array_start_index, is_overflow_default_tick_array_bitmap, tick_array_address,
+    tick_array_bitmap_extension_address,
+};
+use super::tick_math::TickRange;
+use crate::instructions::cp_amm::cp_amm_transfer_fee::{PoolTransferFees, epoch_transfer_fee};
+
+pub const RAYDIUM_CLMM_PROGRAM_ID: &str = "devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH";
+
+pub const REWARD_NUM: usize = 3;
+
+const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+const OPEN_POSITION_WITH_TOKEN22_NFT_DISCRIMINATOR: [u8; 8] = [77, 255, 174, 82, 125, 29, 201, 46];
+const INCREASE_LIQUIDITY_V2_DISCRIMINATOR: [u8; 8] = [133, 29, 89, 223, 69, 238, 176, 10];
+const DECREASE_LIQUIDITY_V2_DISCRIMINATOR: [u8; 8] = [58, 127, 188, 62, 79, 82, 196, 96];
+const SWAP_V2_DISCRIMINATOR: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+const INITIALIZE_REWARD_DISCRIMINATOR: [u8; 8] = [95, 135, 192, 196, 242, 129, 230, 68];
+const SET_REWARD_PARAMS_DISCRIMINATOR: [u8; 8] = [112, 52, 167, 75, 32, 201, 211, 137];
+const COLLECT_REMAINING_REWARDS_DISCRIMINATOR: [u8; 8] = [18, 237, 166, 197, 34, 16, 213, 144];
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct OpenPositionInstructionData {
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub tick_array_lower_start_index: i32,
+    pub tick_array_upper_start_index: i32,
+    pub liquidity: u128,
+    pub amount_0_max: u64,
+    pub amount_1_max: u64,
+    pub with_metadata: bool,
+    pub base_flag: Option<bool>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct IncreaseLiquidityInstructionData {
+    pub liquidity: u128,
+    pub amount_0_max: u64,
+    pub amount_1_max: u64,
+    pub base_flag: Option<bool>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DecreaseLiquidityInstructionData {
+    pub liquidity: u128,
+    pub amount_0_min: u64,
+    pub amount_1_min: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SwapInstructionData {
+    pub amount: u64,
+    pub other_amount_threshold: u64,
+    pub sqrt_price_limit_x64: u128,
+    pub is_base_input: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct InitializeRewardInstructionData {
+    pub open_time: u64,
+    pub end_time: u64,
+    pub emissions_per_second_x64: u128,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetRewardParamsInstructionData {
+    pub reward_index: u8,
+    pub emissions_per_second_x64: u128,
+    pub open_time: u64,
+    pub end_time: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewardState {
+    Uninitialized,
+    Initialized,
+    Opening,
+    Ended,
+}
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone, Copy, Default)]
+pub struct RewardInfo {
+    pub reward_state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    pub emissions_per_second_x64: u128,
+    pub reward_total_emissioned: u64,
+    pub reward_claimed: u64,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub authority: Pubkey,
+    pub reward_growth_global_x64: u128,
+}
+
+impl RewardInfo {
+    pub fn state(&self) -> RewardState {
+        match self.reward_state {
+            0 => RewardState::Uninitialized,
+            1 => RewardState::Initialized,
+            2 => RewardState::Opening,
+            _ => RewardState::Ended,
+        }
+    }
+
+    pub fn initialized(&self) -> bool {
+        self.token_mint != Pubkey::default()
+    }
+}
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct PoolState {
+    pub bump: [u8; 1],
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub padding3: u16,
+    pub padding4: u16,
+    pub fee_growth_global_0_x64: u128,
+    pub fee_growth_global_1_x64: u128,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+    pub status: u8,
+    pub padding: [u8; 7],
+    pub reward_infos: [RewardInfo; REWARD_NUM],
+    pub tick_array_bitmap: [u64; 16],
+    pub total_fees_token_0: u64,
+    pub total_fees_claimed_token_0: u64,
+    pub total_fees_token_1: u64,
+    pub total_fees_claimed_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    pub open_time: u64,
+    pub recent_epoch: u64,
+    pub padding1: [u64; 24],
+    pub padding2: [u64; 32],
+}
+
+impl PoolState {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != POOL_STATE_DISCRIMINATOR {
+            return Err(format_err!("Account is not a CLMM PoolState"));
+        }
+        Ok(PoolState::deserialize(&mut &data[8..])?)
+    }
+
+    /// Current price as token 1 per token 0, decimals applied.
+    pub fn price(&self) -> f64 {
+        sqrt_price_x64_to_price(
+            self.sqrt_price_x64,
+            self.mint_decimals_0,
+            self.mint_decimals_1,
+        )
+    }
+}
+
+pub fn fetch_pool_state(rpc_client: &RpcClient, pool_id: &Pubkey) -> Result<PoolState> {
+    let program_id = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?;
+    let pool_account = rpc_client.get_account(pool_id)?;
+    if pool_account.owner != program_id {
+        return Err(format_err!(
+            "Account {} is not owned by the CLMM program",
+            pool_id
+        ));
+    }
+    PoolState::from_account_data(&pool_account.data)
+}
+
+/// A reward stream and the owner's account for its mint, which receives
+/// the reward or, for the stream's authority, funds it.
+#[derive(Debug, Clone, Copy)]
+pub struct RewardAccounts {
+    pub reward_mint: Pubkey,
+    pub reward_vault: Pubkey,
+    pub token_program: Pubkey,
+    pub recipient: Pubkey,
+}
+
+/// Everything needed to build instructions against a single CLMM pool for a
+/// given owner, resolved from the on-chain `PoolState` and its mints.
+#[derive(Debug, Clone)]
+pub struct ClmmPool {
+    pub program_id: Pubkey,
+    pub pool_id: Pubkey,
+    pub state: PoolState,
+    pub owner: Pubkey,
+    pub owner_token_0_ata: Pubkey,
+    pub owner_token_1_ata: Pubkey,
+}
+
+impl ClmmPool {
+    pub fn load(rpc_client: &RpcClient, pool_id: &Pubkey, owner: &Pubkey) -> Result<Self> {
+        let program_id = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?;
+        let state = fetch_pool_state(rpc_client, pool_id)?;
+
+        let mut mint_accounts =
+            rpc_client.get_multiple_accounts(&[state.token_mint_0, state.token_mint_1])?;
+        let token_program_0 = mint_accounts
+            .remove(0)
+            .ok_or_else(|| format_err!("Token 0 mint not found"))?
+            .owner;
+        let token_program_1 = mint_accounts
+            .remove(0)
+            .ok_or_else(|| format_err!("Token 1 mint not found"))?
+            .owner;
+
+        let owner_token_0_ata = get_associated_token_address_with_program_id(
+            owner,
+            &state.token_mint_0,
+            &token_program_0,
+        );
+        let owner_token_1_ata = get_associated_token_address_with_program_id(
+            owner,
+            &state.token_mint_1,
+            &token_program_1,
+        );
+
+        Ok(Self {
+            program_id,
+            pool_id: *pool_id,
+            state,
+            owner: *owner,
+            owner_token_0_ata,
+            owner_token_1_ata,
+        })
+    }
+
+    pub fn ensure_owner(&self, owner: &Pubkey) -> Result<()> {
+        if self.owner != *owner {
+            return Err(format_err!(
+                "Pool handle was loaded for {}, not {}",
+                self.owner,
+                owner
+            ));
+        }
+        Ok(())
+    }
+
+    /// Re-reads the pool state and both mints in one round trip, returning
+    /// the transfer fees the mints charge in the current epoch.
+    pub fn refresh(&mut self, rpc_client: &RpcClient) -> Result<PoolTransferFees> {
+        let epoch = rpc_client.get_epoch_info()?.epoch;
+        let mut accounts = rpc_client.get_multiple_accounts(&[
+            self.pool_id,
+            self.state.token_mint_0,
+            self.state.token_mint_1,
+        ])?;
+
+        let pool_account = accounts
+            .remove(0)
+            .ok_or_else(|| format_err!("Pool state account not found"))?;
+        self.state = PoolState::from_account_data(&pool_account.data)?;
+
+        let token_mint_0 = accounts
+            .remove(0)
+            .ok_or_else(|| format_err!("Token 0 mint not found"))?;
+        let token_mint_1 = accounts
+            .remove(0)
+            .ok_or_else(|| format_err!("Token 1 mint not found"))?;
+
+        Ok(PoolTransferFees {
+            token_0: epoch_transfer_fee(&token_mint_0.data, &token_mint_0.owner, epoch)?,
+            token_1: epoch_transfer_fee(&token_mint_1.data, &token_mint_1.owner, epoch)?,
+        })
+    }
+
+    /// The lower and upper tick arrays holding `tick_range`, by start index.
+    pub fn tick_array_start_indexes(&self, tick_range: TickRange) -> (i32, i32) {
+        (
+            get_array_start_index(tick_range.tick_lower, self.state.tick_spacing),
+            get_array_start_index(tick_range.tick_upper, self.state.tick_spacing),
+        )
+    }
+
+    /// The bitmap extension, if either end of `tick_range` lies outside the
+    /// pool's default bitmap.
+    fn bitmap_extension_for(&self, tick_range: TickRange) -> Option<AccountMeta> {
+        let tick_spacing = self.state.tick_spacing;
+        if is_overflow_default_tick_array_bitmap(tick_range.tick_lower, tick_spacing)
+            || is_overflow_default_tick_array_bitmap(tick_range.tick_upper, tick_spacing)
+        {
+            Some(AccountMeta::new(
+                tick_array_bitmap_extension_address(&self.program_id, &self.pool_id),
+                false,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Opens a position over `tick_range` whose NFT is minted to the owner
+    /// under Token-2022. The program creates either tick array if needed.
+    pub fn open_position_instruction(
+        &self,
+        position_nft_mint: &Pubkey,
+        tick_range: TickRange,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    ) -> Result<Instruction> {
+        let (tick_array_lower_start_index, tick_array_upper_start_index) =
+            self.tick_array_start_indexes(tick_range);
+        let instruction_data = OpenPositionInstructionData {
+            tick_lower_index: tick_range.tick_lower,
+            tick_upper_index: tick_range.tick_upper,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            with_metadata: false,
+            base_flag: None,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 50);
+        data_with_discriminator.extend_from_slice(&OPEN_POSITION_WITH_TOKEN22_NFT_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        let (tick_array_lower, tick_array_upper) = self.tick_array_accounts(tick_range);
+        let position_nft_account = get_associated_token_address_with_program_id(
+            &self.owner,
+            position_nft_mint,
+            &spl_token_2022::id(),
+        );
+
+        let mut accounts = vec![
+            AccountMeta::new(self.owner, true),
+            AccountMeta::new_readonly(self.owner, false),
+            AccountMeta::new(*position_nft_mint, true),
+            AccountMeta::new(position_nft_account, false),
+            AccountMeta::new(self.pool_id, false),
+            self.protocol_position_account(tick_range),
+            tick_array_lower,
+            tick_array_upper,
+            AccountMeta::new(
+                personal_position_address(&self.program_id, position_nft_mint),
+                false,
+            ),
+            AccountMeta::new(self.owner_token_0_ata, false),
+            AccountMeta::new(self.owner_token_1_ata, false),
+            AccountMeta::new(self.state.token_vault_0, false),
+            AccountMeta::new(self.state.token_vault_1, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(self.state.token_mint_0, false),
+            AccountMeta::new_readonly(self.state.token_mint_1, false),
+        ];
+        accounts.extend(self.bitmap_extension_for(tick_range));
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: data_with_discriminator,
+        })
+    }
+
+    /// The tick arrays holding both ends of `tick_range`.
+    fn tick_array_accounts(&self, tick_range: TickRange) -> (AccountMeta, AccountMeta) {
+        let (lower_start_index, upper_start_index) = self.tick_array_start_indexes(tick_range);
+        (
+            AccountMeta::new(
+                tick_array_address(&self.program_id, &self.pool_id, lower_start_index),
+                false,
+            ),
+            AccountMeta::new(
+                tick_array_address(&self.program_id, &self.pool_id, upper_start_index),
+                false,
+            ),
+        )
+    }
+
+    fn protocol_position_account(&self, tick_range: TickRange) -> AccountMeta {
+        AccountMeta::new(
+            protocol_position_address(
+                &self.program_id,
+                &self.pool_id,
+                tick_range.tick_lower,
+                tick_range.tick_upper,
+            ),
+            false,
+        )
+    }
+
+    /// Resolves the pool's initialised reward streams and the owner's
+    /// associated token account for each.
+    pub fn fetch_reward_accounts(&self, rpc_client: &RpcClient) -> Result<Vec<RewardAccounts>> {
+        let reward_infos: Vec<_> = self
+            .state
+            .reward_infos
+            .iter()
+            .filter(|reward_info| reward_info.initialized())
+            .collect();
+        let reward_mints: Vec<_> = reward_infos
+            .iter()
+            .map(|reward_info| reward_info.token_mint)
+            .collect();
+        let mint_accounts = rpc_client.get_multiple_accounts(&reward_mints)?;
+
+        reward_infos
+            .into_iter()
+            .zip(mint_accounts)
+            .map(|(reward_info, mint_account)| {
+                let token_program = mint_account
+                    .ok_or_else(|| format_err!("Reward mint {} not found", reward_info.token_mint))?
+                    .owner;
+                Ok(RewardAccounts {
+                    reward_mint: reward_info.token_mint,
+                    reward_vault: reward_info.token_vault,
+                    token_program,
+                    recipient: get_associated_token_address_with_program_id(
+                        &self.owner,
+                        &reward_info.token_mint,
+                        &token_program,
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// The slot holding the reward stream for `reward_mint`.
+    pub fn reward_index(&self, reward_mint: &Pubkey) -> Result<u8> {
+        self.state
+            .reward_infos
+            .iter()
+            .position(|reward_info| {
+                reward_info.initialized() && reward_info.token_mint == *reward_mint
+            })
+            .map(|index| index as u8)
+            .ok_or_else(|| {
+                format_err!(
+                    "Pool {} has no reward stream for {}",
+                    self.pool_id,
+                    reward_mint
+                )
+            })
+    }
+
+    /// The accounts for a reward stream in `reward_mint`, whether or not it
+    /// exists yet, with the owner's associated token account as recipient.
+    pub fn reward_accounts_for(
+        &self,
+        reward_mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> RewardAccounts {
+        RewardAccounts {
+            reward_mint: *reward_mint,
+            reward_vault: reward_vault_address(&self.program_id, &self.pool_id, reward_mint),
+            token_program: *token_program,
+            recipient: get_associated_token_address_with_program_id(
+                &self.owner,
+                reward_mint,
+                token_program,
+            ),
+        }
+    }
+
+    /// Opens a new reward stream on the pool, funded in full from the
+    /// owner's account for the reward mint. The reward vault is created by
+    /// the program.
+    pub fn initialize_reward_instruction(
+        &self,
+        reward: &RewardAccounts,
+        schedule: &RewardSchedule,
+    ) -> Result<Instruction> {
+        let instruction_data = InitializeRewardInstructionData {
+            open_time: schedule.open_time,
+            end_time: schedule.end_time,
+            emissions_per_second_x64: schedule.emissions_per_second_x64,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 32);
+        data_with_discriminator.extend_from_slice(&INITIALIZE_REWARD_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(self.owner, true),
+                AccountMeta::new(reward.recipient, false),
+                AccountMeta::new_readonly(self.state.amm_config, false),
+                AccountMeta::new(self.pool_id, false),
+                AccountMeta::new_readonly(operation_state_address(&self.program_id), false),
+                AccountMeta::new_readonly(reward.reward_mint, false),
+                AccountMeta::new(reward.reward_vault, false),
+                AccountMeta::new_readonly(reward.token_program, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+            ],
+            data: data_with_discriminator,
+        })
+    }
+
+    /// Restarts, extends or raises the emissions of the reward stream in
+    /// slot `reward_index`. Any extra funding is drawn from the owner's
+    /// account for the reward mint.
+    pub fn set_reward_params_instruction(
+        &self,
+        reward_index: u8,
+        reward: &RewardAccounts,
+        schedule: &RewardSchedule,
+    ) -> Result<Instruction> {
+        let instruction_data = SetRewardParamsInstructionData {
+            reward_index,
+            emissions_per_second_x64: schedule.emissions_per_second_x64,
+            open_time: schedule.open_time,
+            end_time: schedule.end_time,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 33);
+        data_with_discriminator.extend_from_slice(&SET_REWARD_PARAMS_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.owner, true),
+                AccountMeta::new_readonly(self.state.amm_config, false),
+                AccountMeta::new(self.pool_id, false),
+                AccountMeta::new_readonly(operation_state_address(&self.program_id), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_token_2022::id(), false),
+                // Read only when the change needs funding.
+                AccountMeta::new(reward.reward_vault, false),
+                AccountMeta::new(reward.recipient, false),
+                AccountMeta::new_readonly(reward.reward_mint, false),
+            ],
+            data: data_with_discriminator,
+        })
+    }
+
+    /// Returns what is left in an ended reward stream's vault, beyond what
+    /// positions are still owed, to the owner's account for the reward mint.
+    pub fn collect_remaining_rewards_instruction(
+        &self,
+        reward_index: u8,
+        reward: &RewardAccounts,
+    ) -> Result<Instruction> {
+        let mut data_with_discriminator = Vec::with_capacity(8 + 1);
+        data_with_discriminator.extend_from_slice(&COLLECT_REMAINING_REWARDS_DISCRIMINATOR);
+        data_with_discriminator.push(reward_index);
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(self.owner, true),
+                AccountMeta::new(reward.recipient, false),
+                AccountMeta::new(self.pool_id, false),
+                AccountMeta::new(reward.reward_vault, false),
+                AccountMeta::new_readonly(reward.reward_mint, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(spl_token_2022::id(), false),
+                AccountMeta::new_readonly(spl_memo::id(), false),
+            ],
+            data: data_with_discriminator,
+        })
+    }
+
+    /// Adds `liquidity` to an existing position, paying at most
+    /// `amount_0_max` and `amount_1_max` including transfer fees.
+    pub fn increase_liquidity_instruction(
+        &self,
+        position: &ClmmPosition,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+    ) -> Result<Instruction> {
+        position.ensure_pool(&self.pool_id)?;
+        let tick_range = position.tick_range();
+        let instruction_data = IncreaseLiquidityInstructionData {
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            base_flag: None,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 33);
+        data_with_discriminator.extend_from_slice(&INCREASE_LIQUIDITY_V2_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        let (tick_array_lower, tick_array_upper) = self.tick_array_accounts(tick_range);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new_readonly(position.nft_account, false),
+            AccountMeta::new(self.pool_id, false),
+            self.protocol_position_account(tick_range),
+            AccountMeta::new(position.personal_position, false),
+            tick_array_lower,
+            tick_array_upper,
+            AccountMeta::new(self.owner_token_0_ata, false),
+            AccountMeta::new(self.owner_token_1_ata, false),
+            AccountMeta::new(self.state.token_vault_0, false),
+            AccountMeta::new(self.state.token_vault_1, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(self.state.token_mint_0, false),
+            AccountMeta::new_readonly(self.state.token_mint_1, false),
+        ];
+        accounts.extend(self.bitmap_extension_for(tick_range));
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: data_with_discriminator,
+        })
+    }
+
+    /// Removes `liquidity` from a position, receiving at least `amount_0_min`
+    /// and `amount_1_min` after transfer fees. Fees owed to the position are
+    /// always collected, along with rewards for every stream in
+    /// `reward_accounts`, so a zero `liquidity` just harvests.
+    pub fn decrease_liquidity_instruction(
+        &self,
+        position: &ClmmPosition,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        reward_accounts: &[RewardAccounts],
+    ) -> Result<Instruction> {
+        position.ensure_pool(&self.pool_id)?;
+        let tick_range = position.tick_range();
+        let instruction_data = DecreaseLiquidityInstructionData {
+            liquidity,
+            amount_0_min,
+            amount_1_min,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 32);
+        data_with_discriminator.extend_from_slice(&DECREASE_LIQUIDITY_V2_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        let (tick_array_lower, tick_array_upper) = self.tick_array_accounts(tick_range);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new_readonly(position.nft_account, false),
+            AccountMeta::new(position.personal_position, false),
+            AccountMeta::new(self.pool_id, false),
+            self.protocol_position_account(tick_range),
+            AccountMeta::new(self.state.token_vault_0, false),
+            AccountMeta::new(self.state.token_vault_1, false),
+            tick_array_lower,
+            tick_array_upper,
+            AccountMeta::new(self.owner_token_0_ata, false),
+            AccountMeta::new(self.owner_token_1_ata, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_memo::id(), false),
+            AccountMeta::new_readonly(self.state.token_mint_0, false),
+            AccountMeta::new_readonly(self.state.token_mint_1, false),
+        ];
+        // The bitmap extension, when needed, must come before the reward
+        // accounts, which the program reads in groups of three.
+        accounts.extend(self.bitmap_extension_for(tick_range));
+        for reward in reward_accounts {
+            accounts.push(AccountMeta::new(reward.reward_vault, false));
+            accounts.push(AccountMeta::new(reward.recipient, false));
+            accounts.push(AccountMeta::new_readonly(reward.reward_mint, false));
+        }
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: data_with_discriminator,
+        })
+    }
+
+    pub fn is_token_0(&self, mint: &Pubkey) -> Result<bool> {
+        if *mint == self.state.token_mint_0 {
+            Ok(true)
+        } else if *mint == self.state.token_mint_1 {
+            Ok(false)
+        } else {
+            Err(format_err!(
+                "Mint {} is not part of pool {}",
+                mint,
+                self.pool_id
+            ))
+        }
+    }
+
+    /// Swaps token 0 for token 1 (`zero_for_one`) or the reverse. `amount`
+    /// is the input when `is_base_input` and the output otherwise, and
+    /// `other_amount_threshold` bounds the other side. `tick_arrays` must be
+    /// the initialised tick arrays the swap walks through, in order.
+    pub fn swap_instruction(
+        &self,
+        zero_for_one: bool,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool,
+        tick_arrays: &[Pubkey],
+    ) -> Result<Instruction> {
+        let instruction_data = SwapInstructionData {
+            amount,
+            other_amount_threshold,
+            sqrt_price_limit_x64,
+            is_base_input,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 33);
+        data_with_discriminator.extend_from_slice(&SWAP_V2_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        let (input_token_account, output_token_account) = if zero_for_one {
+            (self.owner_token_0_ata, self.owner_token_1_ata)
+        } else {
+            (self.owner_token_1_ata, self.owner_token_0_ata)
+        };
+        let (input_vault, output_vault) = if zero_for_one {
+            (self.state.token_vault_0, self.state.token_vault_1)
+        } else {
+            (self.state.token_vault_1, self.state.token_vault_0)
+        };
+        let (input_mint, output_mint) = if zero_for_one {
+            (self.state.token_mint_0, self.state.token_mint_1)
+        } else {
+            (self.state.token_mint_1, self.state.token_mint_0)
+        };
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new_readonly(self.state.amm_config, false),
+            AccountMeta::new(self.pool_id, false),
+            AccountMeta::new(input_token_account, false),
+            AccountMeta::new(output_token_account, false),
+            AccountMeta::new(input_vault, false),
+            AccountMeta::new(output_vault, false),
+            AccountMeta::new(self.state.observation_key, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(spl_token_2022::id(), false),
+            AccountMeta::new_readonly(spl_memo::id(), false),
+            AccountMeta::new_readonly(input_mint, false),
+            AccountMeta::new_readonly(output_mint, false),
+            // Passed unconditionally: the program only reads it when the swap
+            // reaches tick arrays tracked by the extension.
+            AccountMeta::new(
+                tick_array_bitmap_extension_address(&self.program_id, &self.pool_id),
+                false,
+            ),
+        ];
+        accounts.extend(
+            tick_arrays
+                .iter()
+                .map(|tick_array| AccountMeta::new(*tick_array, false)),
+        );
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: data_with_discriminator,
+        })
+    }
+}
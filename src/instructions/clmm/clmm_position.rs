@@ -0,0 +1,300 @@
+use anyhow::{Result, format_err};
+use borsh::BorshDeserialize;
+use serde::Deserialize;
+use solana_client::{rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_system_interface::program as system_program;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Account as TokenAccount;
+use std::str::FromStr;
+
+use super::clmm_pool::{RAYDIUM_CLMM_PROGRAM_ID, REWARD_NUM};
+use super::tick_math::TickRange;
+
+pub const POSITION_SEED: &[u8] = b"position";
+
+const PERSONAL_POSITION_STATE_DISCRIMINATOR: [u8; 8] = [70, 111, 150, 126, 230, 15, 25, 117];
+
+/// Most accounts `getMultipleAccounts` returns per request.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+const CLOSE_POSITION_DISCRIMINATOR: [u8; 8] = [123, 134, 81, 0, 49, 68, 98, 98];
+
+/// The `PersonalPositionState` belonging to a position NFT.
+pub fn personal_position_address(program_id: &Pubkey, position_nft_mint: &Pubkey) -> Pubkey {
+    let (personal_position, _) =
+        Pubkey::find_program_address(&[POSITION_SEED, position_nft_mint.as_ref()], program_id);
+    personal_position
+}
+
+/// The pool-wide `ProtocolPositionState` for a tick range.
+pub fn protocol_position_address(
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+    tick_lower: i32,
+    tick_upper: i32,
+) -> Pubkey {
+    let (protocol_position, _) = Pubkey::find_program_address(
+        &[
+            POSITION_SEED,
+            pool_id.as_ref(),
+            &tick_lower.to_be_bytes(),
+            &tick_upper.to_be_bytes(),
+        ],
+        program_id,
+    );
+    protocol_position
+}
+
+#[derive(BorshDeserialize, Debug, Clone, Copy, Default)]
+pub struct PositionRewardInfo {
+    pub growth_inside_last_x64: u128,
+    pub reward_amount_owed: u64,
+}
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct PersonalPositionState {
+    pub bump: [u8; 1],
+    pub nft_mint: Pubkey,
+    pub pool_id: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_0_last_x64: u128,
+    pub fee_growth_inside_1_last_x64: u128,
+    pub token_fees_owed_0: u64,
+    pub token_fees_owed_1: u64,
+    pub reward_infos: [PositionRewardInfo; REWARD_NUM],
+    pub recent_epoch: u64,
+    pub padding: [u64; 7],
+}
+
+impl PersonalPositionState {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != PERSONAL_POSITION_STATE_DISCRIMINATOR {
+            return Err(format_err!("Account is not a CLMM PersonalPositionState"));
+        }
+        Ok(PersonalPositionState::deserialize(&mut &data[8..])?)
+    }
+
+    pub fn tick_range(&self) -> TickRange {
+        TickRange {
+            tick_lower: self.tick_lower_index,
+            tick_upper: self.tick_upper_index,
+        }
+    }
+
+    /// Whether fees or rewards were owed as of the position's last update.
+    pub fn has_amounts_owed(&self) -> bool {
+        self.token_fees_owed_0 != 0
+            || self.token_fees_owed_1 != 0
+            || self
+                .reward_infos
+                .iter()
+                .any(|reward_info| reward_info.reward_amount_owed != 0)
+    }
+}
+
+/// Reads the `PersonalPositionState` behind `nft_mint` without checking
+/// who holds the NFT.
+pub fn fetch_personal_position(
+    rpc_client: &RpcClient,
+    nft_mint: &Pubkey,
+) -> Result<PersonalPositionState> {
+    let program_id = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?;
+    let personal_position = personal_position_address(&program_id, nft_mint);
+    let account = rpc_client
+        .get_account(&personal_position)
+        .map_err(|_| format_err!("No CLMM position exists for NFT {}", nft_mint))?;
+    if account.owner != program_id {
+        return Err(format_err!(
+            "Account {} is not owned by the CLMM program",
+            personal_position
+        ));
+    }
+    PersonalPositionState::from_account_data(&account.data)
+}
+
+/// A position held by a given owner: its NFT, the owner's NFT account and
+/// the decoded `PersonalPositionState`.
+#[derive(Debug, Clone)]
+pub struct ClmmPosition {
+    pub nft_mint: Pubkey,
+    pub nft_token_program: Pubkey,
+    pub nft_account: Pubkey,
+    pub personal_position: Pubkey,
+    pub state: PersonalPositionState,
+}
+
+impl ClmmPosition {
+    /// Loads the position behind `nft_mint`, which `owner` must hold in its
+    /// associated token account. The NFT may be under SPL Token or
+    /// Token-2022, depending on how the position was opened.
+    pub fn load(rpc_client: &RpcClient, nft_mint: &Pubkey, owner: &Pubkey) -> Result<Self> {
+        let program_id = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?;
+        let personal_position = personal_position_address(&program_id, nft_mint);
+
+        let mut accounts = rpc_client.get_multiple_accounts(&[*nft_mint, personal_position])?;
+        let nft_token_program = accounts
+            .remove(0)
+            .ok_or_else(|| format_err!("Position NFT mint {} not found", nft_mint))?
+            .owner;
+        let position_account = accounts
+            .remove(0)
+            .ok_or_else(|| format_err!("No CLMM position exists for NFT {}", nft_mint))?;
+        if position_account.owner != program_id {
+            return Err(format_err!(
+                "Account {} is not owned by the CLMM program",
+                personal_position
+            ));
+        }
+        let state = PersonalPositionState::from_account_data(&position_account.data)?;
+
+        let nft_account =
+            get_associated_token_address_with_program_id(owner, nft_mint, &nft_token_program);
+        let holds_nft = rpc_client
+            .get_account(&nft_account)
+            .ok()
+            .and_then(|account| {
+                StateWithExtensions::<TokenAccount>::unpack(&account.data)
+                    .ok()
+                    .map(|token_account| token_account.base.amount == 1)
+            })
+            .unwrap_or(false);
+        if !holds_nft {
+            return Err(format_err!(
+                "{} does not hold position NFT {} in {}",
+                owner,
+                nft_mint,
+                nft_account
+            ));
+        }
+
+        Ok(Self {
+            nft_mint: *nft_mint,
+            nft_token_program,
+            nft_account,
+            personal_position,
+            state,
+        })
+    }
+
+    pub fn ensure_pool(&self, pool_id: &Pubkey) -> Result<()> {
+        if self.state.pool_id != *pool_id {
+            return Err(format_err!(
+                "Position {} belongs to pool {}, not {}",
+                self.nft_mint,
+                self.state.pool_id,
+                pool_id
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn tick_range(&self) -> TickRange {
+        self.state.tick_range()
+    }
+
+    /// Burns the position NFT and closes its token account and
+    /// `PersonalPositionState`, returning their rent to `owner`. Token-2022
+    /// NFT mints are closed as well. The position must be empty.
+    pub fn close_position_instruction(&self, owner: &Pubkey) -> Result<Instruction> {
+        Ok(Instruction {
+            program_id: Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?,
+            accounts: vec![
+                AccountMeta::new(*owner, true),
+                AccountMeta::new(self.nft_mint, false),
+                AccountMeta::new(self.nft_account, false),
+                AccountMeta::new(self.personal_position, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(self.nft_token_program, false),
+            ],
+            data: CLOSE_POSITION_DISCRIMINATOR.to_vec(),
+        })
+    }
+}
+
+/// The parts of a `jsonParsed` token account that identify an NFT.
+#[derive(Deserialize)]
+struct ParsedTokenAccountData {
+    parsed: ParsedTokenAccount,
+}
+
+#[derive(Deserialize)]
+struct ParsedTokenAccount {
+    info: ParsedTokenAccountInfo,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParsedTokenAccountInfo {
+    mint: String,
+    token_amount: ParsedTokenAmount,
+}
+
+#[derive(Deserialize)]
+struct ParsedTokenAmount {
+    amount: String,
+    decimals: u8,
+}
+
+/// Every CLMM position `owner` holds. Token accounts under SPL Token and
+/// Token-2022 holding exactly one token of a zero-decimal mint are treated
+/// as candidate position NFTs, and those with a `PersonalPositionState` are
+/// returned.
+pub fn fetch_owned_positions(rpc_client: &RpcClient, owner: &Pubkey) -> Result<Vec<ClmmPosition>> {
+    let program_id = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID)?;
+
+    let mut candidates = Vec::new();
+    for token_program in [spl_token::id(), spl_token_2022::id()] {
+        for keyed_account in rpc_client
+            .get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(token_program))?
+        {
+            let Ok(data) = serde_json::to_value(&keyed_account.account.data)
+                .and_then(serde_json::from_value::<ParsedTokenAccountData>)
+            else {
+                continue;
+            };
+            let info = data.parsed.info;
+            if info.token_amount.amount != "1" || info.token_amount.decimals != 0 {
+                continue;
+            }
+            candidates.push((
+                Pubkey::from_str(&info.mint)?,
+                token_program,
+                Pubkey::from_str(&keyed_account.pubkey)?,
+            ));
+        }
+    }
+
+    let mut positions = Vec::new();
+    for chunk in candidates.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let addresses: Vec<_> = chunk
+            .iter()
+            .map(|(nft_mint, _, _)| personal_position_address(&program_id, nft_mint))
+            .collect();
+        let accounts = rpc_client.get_multiple_accounts(&addresses)?;
+        for ((&(nft_mint, nft_token_program, nft_account), personal_position), account) in
+            chunk.iter().zip(addresses).zip(accounts)
+        {
+            let Some(account) = account.filter(|account| account.owner == program_id) else {
+                continue;
+            };
+            positions.push(ClmmPosition {
+                nft_mint,
+                nft_token_program,
+                nft_account,
+                personal_position,
+                state: PersonalPositionState::from_account_data(&account.data)?,
+            });
+        }
+    }
+
+    Ok(positions)
+}
@@ -0,0 +1,385 @@
+use anyhow::Result;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use super::big_num::U256;
+use super::clmm_pool::{PoolState, REWARD_NUM};
+use super::clmm_position::PersonalPositionState;
+use super::clmm_reward::fetch_cluster_time;
+use super::clmm_tick_array::{TickState, fetch_tick_arrays, get_array_start_index};
+
+const Q64: u128 = 1 << 64;
+
+/// Fees and rewards a position could collect now, before transfer fees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionAccrual {
+    pub fees_owed_0: u64,
+    pub fees_owed_1: u64,
+    /// Reward mint and amount owed for each initialised reward stream.
+    pub rewards_owed: Vec<(Pubkey, u64)>,
+}
+
+/// The pool's global reward growth brought forward to `timestamp`, as the
+/// program's `update_reward_infos` would before touching a position.
+pub fn reward_growths_global_at(pool_state: &PoolState, timestamp: u64) -> [u128; REWARD_NUM] {
+    let mut growths = [0u128; REWARD_NUM];
+    for (growth, reward_info) in growths.iter_mut().zip(&pool_state.reward_infos) {
+        *growth = reward_info.reward_growth_global_x64;
+        if !reward_info.initialized()
+            || timestamp <= reward_info.open_time
+            || pool_state.liquidity == 0
+        {
+            continue;
+        }
+        let time_delta = timestamp
+            .min(reward_info.end_time)
+            .saturating_sub(reward_info.last_update_time);
+        let growth_delta = U256::from(time_delta)
+            .mul_div_floor(
+                U256::from(reward_info.emissions_per_second_x64),
+                U256::from(pool_state.liquidity),
+            )
+            .unwrap_or_default();
+        *growth = growth.wrapping_add(growth_delta.low_u128());
+    }
+    growths
+}
+
+/// Growth per unit of liquidity inside `[tick_lower, tick_upper)` given the
+/// global growth and each bound's growth outside, mirroring the program's
+/// wrapping arithmetic.
+fn growth_inside(
+    tick_current: i32,
+    tick_lower: i32,
+    lower_outside: u128,
+    tick_upper: i32,
+    upper_outside: u128,
+    global: u128,
+) -> u128 {
+    let below = if tick_current >= tick_lower {
+        lower_outside
+    } else {
+        global.wrapping_sub(lower_outside)
+    };
+    let above = if tick_current < tick_upper {
+        upper_outside
+    } else {
+        global.wrapping_sub(upper_outside)
+    };
+    global.wrapping_sub(below).wrapping_sub(above)
+}
+
+/// Fee growth inside the range for tokens 0 and 1.
+pub fn get_fee_growth_inside(
+    pool_state: &PoolState,
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+) -> (u128, u128) {
+    (
+        growth_inside(
+            pool_state.tick_current,
+            tick_lower.tick,
+            tick_lower.fee_growth_outside_0_x64,
+            tick_upper.tick,
+            tick_upper.fee_growth_outside_0_x64,
+            pool_state.fee_growth_global_0_x64,
+        ),
+        growth_inside(
+            pool_state.tick_current,
+            tick_lower.tick,
+            tick_lower.fee_growth_outside_1_x64,
+            tick_upper.tick,
+            tick_upper.fee_growth_outside_1_x64,
+            pool_state.fee_growth_global_1_x64,
+        ),
+    )
+}
+
+/// Reward growth inside the range for each reward stream, zero for
+/// uninitialised ones.
+pub fn get_reward_growths_inside(
+    pool_state: &PoolState,
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+    reward_growths_global: &[u128; REWARD_NUM],
+) -> [u128; REWARD_NUM] {
+    let mut growths = [0u128; REWARD_NUM];
+    for (i, growth) in growths.iter_mut().enumerate() {
+        if pool_state.reward_infos[i].initialized() {
+            *growth = growth_inside(
+                pool_state.tick_current,
+                tick_lower.tick,
+                tick_lower.reward_growths_outside_x64[i],
+                tick_upper.tick,
+                tick_upper.reward_growths_outside_x64[i],
+                reward_growths_global[i],
+            );
+        }
+    }
+    growths
+}
+
+/// `owed` plus what `liquidity` earned as growth moved from `last` to
+/// `current`. Like the program's `to_underflow_u64`, an amount that does not
+/// fit below `u64::MAX` counts as nothing earned.
+fn accrued(owed: u64, current: u128, last: u128, liquidity: u128) -> u64 {
+    let earned = U256::from(current.wrapping_sub(last))
+        .mul_div_floor(U256::from(liquidity), U256::from(Q64))
+        .unwrap_or_default();
+    let earned = if earned < U256::from(u64::MAX) {
+        earned.low_u64()
+    } else {
+        0
+    };
+    owed.wrapping_add(earned)
+}
+
+/// Everything `position` has earned up to `timestamp`, given the states of
+/// its lower and upper ticks.
+pub fn compute_position_accrual(
+    pool_state: &PoolState,
+    position: &PersonalPositionState,
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+    timestamp: u64,
+) -> PositionAccrual {
+    let (fee_growth_inside_0, fee_growth_inside_1) =
+        get_fee_growth_inside(pool_state, tick_lower, tick_upper);
+    let reward_growths_inside = get_reward_growths_inside(
+        pool_state,
+        tick_lower,
+        tick_upper,
+        &reward_growths_global_at(pool_state, timestamp),
+    );
+
+    let rewards_owed = pool_state
+        .reward_infos
+        .iter()
+        .zip(&position.reward_infos)
+        .zip(reward_growths_inside)
+        .filter(|((reward_info, _), _)| reward_info.initialized())
+        .map(|((reward_info, position_reward), growth_inside)| {
+            (
+                reward_info.token_mint,
+                accrued(
+                    position_reward.reward_amount_owed,
+                    growth_inside,
+                    position_reward.growth_inside_last_x64,
+                    position.liquidity,
+                ),
+            )
+        })
+        .collect();
+
+    PositionAccrual {
+        fees_owed_0: accrued(
+            position.token_fees_owed_0,
+            fee_growth_inside_0,
+            position.fee_growth_inside_0_last_x64,
+            position.liquidity,
+        ),
+        fees_owed_1: accrued(
+            position.token_fees_owed_1,
+            fee_growth_inside_1,
+            position.fee_growth_inside_1_last_x64,
+            position.liquidity,
+        ),
+        rewards_owed,
+    }
+}
+
+/// Reads the position's tick states and the cluster time, then computes
+/// what it has earned.
+pub fn fetch_position_accrual(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+    pool_state: &PoolState,
+    position: &PersonalPositionState,
+) -> Result<PositionAccrual> {
+    let tick_spacing = pool_state.tick_spacing;
+    let tick_range = position.tick_range();
+    let lower_start_index = get_array_start_index(tick_range.tick_lower, tick_spacing);
+    let upper_start_index = get_array_start_index(tick_range.tick_upper, tick_spacing);
+    let tick_arrays = fetch_tick_arrays(
+        rpc_client,
+        program_id,
+        pool_id,
+        &[lower_start_index, upper_start_index],
+    )?;
+    let tick_lower = tick_arrays[0].tick(tick_range.tick_lower, tick_spacing)?;
+    let tick_upper = tick_arrays[1].tick(tick_range.tick_upper, tick_spacing)?;
+
+    let timestamp = fetch_cluster_time(rpc_client)?;
+
+    Ok(compute_position_accrual(
+        pool_state, position, tick_lower, tick_upper, timestamp,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+
+    fn pool_state(tick_current: i32, liquidity: u128) -> PoolState {
+        let mut pool_state = PoolState::deserialize(&mut &[0u8; 1536][..]).unwrap();
+        pool_state.tick_current = tick_current;
+        pool_state.liquidity = liquidity;
+        pool_state
+    }
+
+    fn tick(
+        tick: i32,
+        fee_growth_outside_0_x64: u128,
+        fee_growth_outside_1_x64: u128,
+    ) -> TickState {
+        TickState {
+            tick,
+            fee_growth_outside_0_x64,
+            fee_growth_outside_1_x64,
+            ..Default::default()
+        }
+    }
+
+    fn position(liquidity: u128) -> PersonalPositionState {
+        let mut position = PersonalPositionState::deserialize(&mut &[0u8; 273][..]).unwrap();
+        position.tick_lower_index = -60;
+        position.tick_upper_index = 60;
+        position.liquidity = liquidity;
+        position
+    }
+
+    #[test]
+    fn fee_growth_inside_by_price_position() {
+        let mut pool_state = pool_state(0, 1);
+        pool_state.fee_growth_global_0_x64 = 1000;
+        pool_state.fee_growth_global_1_x64 = 2000;
+
+        // Below the range: lower outside minus upper outside.
+        pool_state.tick_current = -61;
+        let (lower, upper) = (tick(-60, 700, 1500), tick(60, 200, 400));
+        assert_eq!(
+            get_fee_growth_inside(&pool_state, &lower, &upper),
+            (500, 1100)
+        );
+
+        // Inside the range, on the lower bound.
+        pool_state.tick_current = -60;
+        let (lower, upper) = (tick(-60, 300, 500), tick(60, 100, 200));
+        assert_eq!(
+            get_fee_growth_inside(&pool_state, &lower, &upper),
+            (600, 1300)
+        );
+
+        // Above the range, on the upper bound: upper outside minus lower.
+        pool_state.tick_current = 60;
+        let (lower, upper) = (tick(-60, 200, 300), tick(60, 700, 1800));
+        assert_eq!(
+            get_fee_growth_inside(&pool_state, &lower, &upper),
+            (500, 1500)
+        );
+    }
+
+    #[test]
+    fn fee_growth_inside_wraps_around_u128() {
+        let mut pool_state = pool_state(0, 1);
+        // The global growth has wrapped past zero since the lower tick was
+        // crossed, so its outside value is "negative".
+        pool_state.fee_growth_global_0_x64 = 50;
+        pool_state.fee_growth_global_1_x64 = 0;
+        let lower = tick(-60, 0u128.wrapping_sub(50), 0u128.wrapping_sub(1));
+        let upper = tick(60, 0, 0);
+        assert_eq!(get_fee_growth_inside(&pool_state, &lower, &upper), (100, 1));
+    }
+
+    #[test]
+    fn accrual_across_a_wrapped_growth() {
+        let last = 0u128.wrapping_sub(Q64);
+        assert_eq!(accrued(7, Q64, last, 5), 17);
+    }
+
+    #[test]
+    fn accrual_above_u64_counts_as_nothing_earned() {
+        assert_eq!(accrued(0, u128::from(u64::MAX - 1), 0, Q64), u64::MAX - 1);
+        assert_eq!(accrued(7, u128::from(u64::MAX), 0, Q64), 7);
+        assert_eq!(accrued(7, 2 * Q64, 0, Q64), 7);
+    }
+
+    #[test]
+    fn accrual_rounds_down() {
+        // Three quarters of a token per unit, times one unit.
+        assert_eq!(accrued(0, 3 * Q64 / 4, 0, 1), 0);
+        assert_eq!(accrued(0, 3 * Q64 / 4, 0, 3), 2);
+    }
+
+    fn rewarding_pool(liquidity: u128) -> PoolState {
+        let mut pool_state = pool_state(0, liquidity);
+        let reward_info = &mut pool_state.reward_infos[0];
+        reward_info.token_mint = Pubkey::new_unique();
+        reward_info.open_time = 1000;
+        reward_info.end_time = 2000;
+        reward_info.last_update_time = 1000;
+        reward_info.emissions_per_second_x64 = 3 * Q64;
+        reward_info.reward_growth_global_x64 = 7;
+        pool_state
+    }
+
+    #[test]
+    fn reward_growth_before_open_time_is_unchanged() {
+        let pool_state = rewarding_pool(2);
+        assert_eq!(reward_growths_global_at(&pool_state, 500)[0], 7);
+        assert_eq!(reward_growths_global_at(&pool_state, 1000)[0], 7);
+    }
+
+    #[test]
+    fn reward_growth_accrues_until_end_time() {
+        let pool_state = rewarding_pool(2);
+        assert_eq!(
+            reward_growths_global_at(&pool_state, 1500)[0],
+            7 + 750 * Q64
+        );
+        assert_eq!(
+            reward_growths_global_at(&pool_state, 2000)[0],
+            7 + 1500 * Q64
+        );
+        assert_eq!(
+            reward_growths_global_at(&pool_state, 5000)[0],
+            7 + 1500 * Q64
+        );
+    }
+
+    #[test]
+    fn reward_growth_without_liquidity_is_unchanged() {
+        let pool_state = rewarding_pool(0);
+        assert_eq!(reward_growths_global_at(&pool_state, 1500)[0], 7);
+        // Uninitialised streams are left alone too.
+        assert_eq!(reward_growths_global_at(&rewarding_pool(2), 1500)[1], 0);
+    }
+
+    #[test]
+    fn position_accrual_adds_fees_and_rewards() {
+        let mut pool_state = rewarding_pool(2);
+        pool_state.fee_growth_global_0_x64 = 10 * Q64;
+        pool_state.fee_growth_global_1_x64 = 4 * Q64;
+        let mut position = position(2);
+        position.fee_growth_inside_0_last_x64 = 6 * Q64;
+        position.token_fees_owed_0 = 1;
+        position.reward_infos[0].growth_inside_last_x64 = 7;
+        position.reward_infos[0].reward_amount_owed = 5;
+
+        let accrual = compute_position_accrual(
+            &pool_state,
+            &position,
+            &tick(-60, 0, 0),
+            &tick(60, 0, 0),
+            1500,
+        );
+        assert_eq!(accrual.fees_owed_0, 1 + 4 * 2);
+        assert_eq!(accrual.fees_owed_1, 4 * 2);
+        assert_eq!(
+            accrual.rewards_owed,
+            vec![(pool_state.reward_infos[0].token_mint, 5 + 750 * 2)]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{Result, format_err};
+
+use super::big_num::U256;
+use super::tick_math::{
+    MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64, TickRange, ceil_tick_to_spacing, floor_tick_to_spacing,
+    get_sqrt_price_at_tick, get_tick_at_sqrt_price,
+};
+
+// Prices throughout are token 1 per token 0 in whole tokens, so the raw
+// on-chain price is `price * 10^(decimals_1 - decimals_0)`.
+
+/// Splits a positive finite `value` into `mantissa * 2^exponent` exactly.
+fn decompose(value: f64) -> (u64, i32) {
+    let bits = value.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    if exponent == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), exponent - 1075)
+    }
+}
+
+fn pow10(exponent: u32) -> Result<U256> {
+    U256::from(10)
+        .checked_pow(U256::from(exponent))
+        .ok_or_else(|| format_err!("10^{} does not fit in 256 bits", exponent))
+}
+
+fn u256_to_f64(value: U256) -> f64 {
+    let shift = value.bits().saturating_sub(64);
+    (value >> shift).low_u64() as f64 * 2f64.powi(shift as i32)
+}
+
+/// The Q64.64 sqrt price for `price`, rounded down. Exact for the given
+/// `f64`, so no precision is lost beyond the input's own.
+pub fn price_to_sqrt_price_x64(price: f64, decimals_0: u8, decimals_1: u8) -> Result<u128> {
+    if !price.is_finite() || price <= 0.0 {
+        return Err(format_err!(
+            "Price must be a positive number, got {}",
+            price
+        ));
+    }
+
+    // Rough range check first so the exact computation below cannot
+    // overflow.
+    let decimals_diff = decimals_1 as i32 - decimals_0 as i32;
+    let approximate = (price * 10f64.powi(decimals_diff)).sqrt() * 2f64.powi(64);
+    if !(approximate >= MIN_SQRT_PRICE_X64 as f64 / 2.0
+        && approximate < MAX_SQRT_PRICE_X64 as f64 * 2.0)
+    {
+        return Err(format_err!(
+            "Price {} is outside the range the CLMM program supports",
+            price
+        ));
+    }
+
+    // Raw price as a Q128.128 number, whose integer square root is the
+    // Q64.64 sqrt price.
+    let (mantissa, exponent) = decompose(price);
+    let mut numerator = U256::from(mantissa);
+    let mut denominator = U256::one();
+    if decimals_diff >= 0 {
+        numerator = numerator
+            .checked_mul(pow10(decimals_diff as u32)?)
+            .ok_or_else(|| format_err!("Price {} overflows the conversion", price))?;
+    } else {
+        denominator = pow10(-decimals_diff as u32)?;
+    }
+    let shift = exponent + 128;
+    let shifted = |value: U256, shift: i32| {
+        if value.leading_zeros() < shift as u32 {
+            Err(format_err!("Price {} overflows the conversion", price))
+        } else {
+            Ok(value << shift as usize)
+        }
+    };
+    let price_x128 = if shift >= 0 {
+        shifted(numerator, shift)? / denominator
+    } else {
+        numerator / shifted(denominator, -shift)?
+    };
+
+    let sqrt_price_x64 = price_x128.integer_sqrt();
+    if sqrt_price_x64 < U256::from(MIN_SQRT_PRICE_X64)
+        || sqrt_price_x64 >= U256::from(MAX_SQRT_PRICE_X64)
+    {
+        return Err(format_err!(
+            "Price {} is outside the range the CLMM program supports",
+            price
+        ));
+    }
+    Ok(sqrt_price_x64.as_u128())
+}
+
+/// The price a Q64.64 sqrt price represents.
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128, decimals_0: u8, decimals_1: u8) -> f64 {
+    let sqrt_price = U256::from(sqrt_price_x64);
+    u256_to_f64(sqrt_price * sqrt_price)
+        * 2f64.powi(-128)
+        * 10f64.powi(decimals_0 as i32 - decimals_1 as i32)
+}
+
+/// The greatest tick whose price is at most `price`.
+pub fn price_to_tick(price: f64, decimals_0: u8, decimals_1: u8) -> Result<i32> {
+    get_tick_at_sqrt_price(price_to_sqrt_price_x64(price, decimals_0, decimals_1)?)
+}
+
+pub fn tick_to_price(tick: i32, decimals_0: u8, decimals_1: u8) -> Result<f64> {
+    Ok(sqrt_price_x64_to_price(
+        get_sqrt_price_at_tick(tick)?,
+        decimals_0,
+        decimals_1,
+    ))
+}
+
+/// The narrowest range of usable ticks that covers `[lower_price,
+/// upper_price]`.
+pub fn price_range_to_ticks(
+    lower_price: f64,
+    upper_price: f64,
+    decimals_0: u8,
+    decimals_1: u8,
+    tick_spacing: u16,
+) -> Result<TickRange> {
+    if tick_spacing == 0 {
+        return Err(format_err!("Tick spacing must be positive"));
+    }
+    let tick_lower = floor_tick_to_spacing(
+        price_to_tick(lower_price, decimals_0, decimals_1)?,
+        tick_spacing,
+    );
+    let tick_upper = ceil_tick_to_spacing(
+        price_to_tick(upper_price, decimals_0, decimals_1)?,
+        tick_spacing,
+    );
+    if tick_lower >= tick_upper {
+        return Err(format_err!(
+            "Lower price {} must be below upper price {}",
+            lower_price,
+            upper_price
+        ));
+    }
+    Ok(TickRange {
+        tick_lower,
+        tick_upper,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::clmm::tick_math::{MAX_TICK, MIN_TICK};
+
+    const DECIMALS: [(u8, u8); 4] = [(6, 6), (9, 6), (6, 9), (0, 12)];
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            ((actual - expected) / expected).abs() < tolerance,
+            "{} vs {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn sqrt_price_at_known_prices() {
+        assert_eq!(price_to_sqrt_price_x64(1.0, 6, 6).unwrap(), 1 << 64);
+        assert_eq!(price_to_sqrt_price_x64(4.0, 6, 6).unwrap(), 2 << 64);
+        assert_eq!(price_to_sqrt_price_x64(0.25, 9, 9).unwrap(), 1 << 63);
+        // One token 0 with 9 decimals for one token 1 with 6 is a raw price
+        // of 10^-3, and the other way round 10^3.
+        assert_eq!(
+            price_to_sqrt_price_x64(1.0, 9, 6).unwrap(),
+            583337266871351588
+        );
+        assert_eq!(
+            price_to_sqrt_price_x64(1.0, 6, 9).unwrap(),
+            583337266871351588485
+        );
+        assert_eq!(
+            price_to_sqrt_price_x64(1000.0, 9, 6).unwrap(),
+            price_to_sqrt_price_x64(1.0, 6, 6).unwrap()
+        );
+    }
+
+    #[test]
+    fn sqrt_price_rejects_invalid_prices() {
+        for price in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(price_to_sqrt_price_x64(price, 6, 6).is_err(), "{}", price);
+        }
+        assert!(price_to_sqrt_price_x64(1e40, 6, 6).is_err());
+        assert!(price_to_sqrt_price_x64(1e-40, 6, 6).is_err());
+        // In range once the decimals are applied.
+        assert!(price_to_sqrt_price_x64(1e-30, 0, 18).is_ok());
+    }
+
+    #[test]
+    fn price_round_trips_through_sqrt_price() {
+        for (decimals_0, decimals_1) in DECIMALS {
+            for price in [1e-6, 0.000123, 0.5, 1.0, 1.5, 97.31, 123_456.789, 1e6] {
+                let sqrt_price_x64 =
+                    price_to_sqrt_price_x64(price, decimals_0, decimals_1).unwrap();
+                assert_close(
+                    sqrt_price_x64_to_price(sqrt_price_x64, decimals_0, decimals_1),
+                    price,
+                    1e-12,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sqrt_price_round_trips_through_price() {
+        for (decimals_0, decimals_1) in DECIMALS {
+            for tick in [-200_000, -1000, -1, 0, 1, 1000, 200_000] {
+                let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+                let price = sqrt_price_x64_to_price(sqrt_price_x64, decimals_0, decimals_1);
+                let round_trip = price_to_sqrt_price_x64(price, decimals_0, decimals_1).unwrap();
+                // Off by at most a unit in the last place of the f64 price.
+                assert_close(round_trip as f64, sqrt_price_x64 as f64, 1e-14);
+            }
+        }
+    }
+
+    #[test]
+    fn tick_at_known_prices() {
+        assert_eq!(price_to_tick(1.0, 6, 6).unwrap(), 0);
+        assert_eq!(price_to_tick(1000.0, 6, 6).unwrap(), 69081);
+        assert_eq!(price_to_tick(1.0, 6, 9).unwrap(), 69081);
+        assert_eq!(price_to_tick(1.0, 9, 6).unwrap(), -69082);
+        assert_eq!(price_to_tick(0.99, 6, 6).unwrap(), -101);
+        assert_eq!(price_to_tick(1.01, 6, 6).unwrap(), 99);
+    }
+
+    #[test]
+    fn tick_round_trips_through_price() {
+        for (decimals_0, decimals_1) in DECIMALS {
+            for tick in (MIN_TICK + 1..MAX_TICK).step_by(9973).chain([-1, 0, 1]) {
+                let price = tick_to_price(tick, decimals_0, decimals_1).unwrap();
+                assert_close(
+                    price,
+                    1.0001f64.powi(tick) * 10f64.powi(decimals_0 as i32 - decimals_1 as i32),
+                    1e-9,
+                );
+                // The price a tick stands for can round either way as an
+                // f64, so step just inside and outside it.
+                assert_eq!(
+                    price_to_tick(price * (1.0 + 1e-9), decimals_0, decimals_1).unwrap(),
+                    tick,
+                    "tick {} with decimals {} and {}",
+                    tick,
+                    decimals_0,
+                    decimals_1
+                );
+                assert_eq!(
+                    price_to_tick(price * (1.0 - 1e-9), decimals_0, decimals_1).unwrap(),
+                    tick - 1,
+                    "tick {} with decimals {} and {}",
+                    tick,
+                    decimals_0,
+                    decimals_1
+                );
+            }
+        }
+    }
+
+    fn range(tick_lower: i32, tick_upper: i32) -> TickRange {
+        TickRange {
+            tick_lower,
+            tick_upper,
+        }
+    }
+
+    #[test]
+    fn price_range_rounds_out_to_the_spacing() {
+        assert_eq!(
+            price_range_to_ticks(0.99, 1.01, 6, 6, 60).unwrap(),
+            range(-120, 120)
+        );
+        assert_eq!(
+            price_range_to_ticks(0.99, 1.01, 6, 6, 1).unwrap(),
+            range(-101, 99)
+        );
+        // The same range in whole tokens with unequal decimals.
+        assert_eq!(
+            price_range_to_ticks(990.0, 1010.0, 9, 6, 60).unwrap(),
+            range(-120, 120)
+        );
+    }
+
+    #[test]
+    fn price_range_keeps_ticks_on_the_spacing() {
+        let lower = tick_to_price(60, 6, 6).unwrap() * (1.0 + 1e-9);
+        let upper = tick_to_price(180, 6, 6).unwrap() * (1.0 + 1e-9);
+        assert_eq!(
+            price_range_to_ticks(lower, upper, 6, 6, 60).unwrap(),
+            range(60, 180)
+        );
+        let lower = tick_to_price(-180, 6, 9).unwrap() * (1.0 + 1e-9);
+        let upper = tick_to_price(-60, 6, 9).unwrap() * (1.0 + 1e-9);
+        assert_eq!(
+            price_range_to_ticks(lower, upper, 6, 9, 60).unwrap(),
+            range(-180, -60)
+        );
+    }
+
+    #[test]
+    fn price_range_rejects_empty_ranges() {
+        assert!(price_range_to_ticks(1.0, 1.0, 6, 6, 60).is_err());
+        assert!(price_range_to_ticks(1.01, 0.99, 6, 6, 60).is_err());
+        assert!(price_range_to_ticks(0.99, 1.01, 6, 6, 0).is_err());
+        // Both prices inside one spacing still round out to it.
+        assert_eq!(
+            price_range_to_ticks(1.0001, 1.0002, 6, 6, 60).unwrap(),
+            range(0, 60)
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::{Result, format_err};
+
+use super::clmm_config::AmmConfig;
+use super::clmm_pool::PoolState;
+use super::clmm_tick_array::TickArrayState;
+use super::liquidity_math::{get_delta_amounts_signed, get_liquidity_from_amounts};
+use super::swap_simulator::simulate_swap;
+use super::tick_math::{MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64, TickRange, get_sqrt_price_at_tick};
+use crate::instructions::cp_amm::cp_amm_transfer_fee::{
+    PoolTransferFees, transfer_fee, transfer_inverse_fee,
+};
+
+/// Liquidity and token amounts for resizing a position at the pool's current
+/// price. `amount_*` is what the pool's vaults change by; the user sends that
+/// plus the transfer fee when adding liquidity, and receives that minus the
+/// fee when removing it.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionQuote {
+    pub tick_range: TickRange,
+    pub liquidity: u128,
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub transfer_fee_0: u64,
+    pub transfer_fee_1: u64,
+}
+
+impl PositionQuote {
+    /// Amounts leaving the wallet, transfer fees included.
+    pub fn deposit_amounts(&self) -> Result<(u64, u64)> {
+        Ok((
+            self.amount_0
+                .checked_add(self.transfer_fee_0)
+                .ok_or_else(|| format_err!("Token 0 amount overflows"))?,
+            self.amount_1
+                .checked_add(self.transfer_fee_1)
+                .ok_or_else(|| format_err!("Token 1 amount overflows"))?,
+        ))
+    }
+
+    /// Amounts arriving in the wallet, transfer fees deducted.
+    pub fn withdraw_amounts(&self) -> (u64, u64) {
+        (
+            self.amount_0 - self.transfer_fee_0,
+            self.amount_1 - self.transfer_fee_1,
+        )
+    }
+}
+
+/// Quotes adding `liquidity` to `tick_range`, rounding amounts up as the
+/// program does.
+pub fn quote_position_from_liquidity(
+    pool_state: &PoolState,
+    tick_range: TickRange,
+    liquidity: u128,
+    transfer_fees: &PoolTransferFees,
+) -> Result<PositionQuote> {
+    let (amount_0, amount_1) = get_delta_amounts_signed(
+        pool_state.tick_current,
+        pool_state.sqrt_price_x64,
+        tick_range.tick_lower,
+        tick_range.tick_upper,
+        i128::try_from(liquidity)?,
+    )?;
+    Ok(PositionQuote {
+        tick_range,
+        liquidity,
+        amount_0,
+        amount_1,
+        transfer_fee_0: transfer_inverse_fee(transfer_fees.token_0.as_ref(), amount_0)?,
+        transfer_fee_1: transfer_inverse_fee(transfer_fees.token_1.as_ref(), amount_1)?,
+    })
+}
+
+/// Quotes the most liquidity `tick_range` can take with at most
+/// `maximum_amount_0` and `maximum_amount_1` leaving the wallet. Either side
+/// may be `None`, in which case it is sized from the other.
+pub fn quote_position_from_amounts(
+    pool_state: &PoolState,
+    tick_range: TickRange,
+    maximum_amount_0: Option<u64>,
+    maximum_amount_1: Option<u64>,
+    transfer_fees: &PoolTransferFees,
+) -> Result<PositionQuote> {
+    if maximum_amount_0.is_none() && maximum_amount_1.is_none() {
+        return Err(format_err!("At least one token amount is required"));
+    }
+    // Out of range, only one side is deposited, so it must be the one given.
+    if pool_state.tick_current < tick_range.tick_lower && maximum_amount_0.is_none() {
+        return Err(format_err!(
+            "Range [{}, {}) is above the price and takes only token 0",
+            tick_range.tick_lower,
+            tick_range.tick_upper
+        ));
+    }
+    if pool_state.tick_current >= tick_range.tick_upper && maximum_amount_1.is_none() {
+        return Err(format_err!(
+            "Range [{}, {}) is below the price and takes only token 1",
+            tick_range.tick_lower,
+            tick_range.tick_upper
+        ));
+    }
+    // Size against what the pool would actually receive.
+    let received = |maximum: Option<u64>, fee| -> Result<u64> {
+        match maximum {
+            Some(maximum) => Ok(maximum - transfer_fee(fee, maximum)?),
+            None => Ok(u64::MAX),
+        }
+    };
+    let liquidity = get_liquidity_from_amounts(
+        pool_state.sqrt_price_x64,
+        get_sqrt_price_at_tick(tick_range.tick_lower)?,
+        get_sqrt_price_at_tick(tick_range.tick_upper)?,
+        received(maximum_amount_0, transfer_fees.token_0.as_ref())?,
+        received(maximum_amount_1, transfer_fees.token_1.as_ref())?,
+    )?;
+    if liquidity == 0 {
+        return Err(format_err!(
+            "Token amounts are too small to add any liquidity to this range"
+        ));
+    }
+    quote_position_from_liquidity(pool_state, tick_range, liquidity, transfer_fees)
+}
+
+/// Quotes removing `liquidity` from `tick_range`, rounding amounts down as
+/// the program does. Fees and rewards already owed to the position are paid
+/// out on top of these amounts.
+pub fn quote_decrease_liquidity(
+    pool_state: &PoolState,
+    tick_range: TickRange,
+    liquidity: u128,
+    transfer_fees: &PoolTransferFees,
+) -> Result<PositionQuote> {
+    let (amount_0, amount_1) = get_delta_amounts_signed(
+        pool_state.tick_current,
+        pool_state.sqrt_price_x64,
+        tick_range.tick_lower,
+        tick_range.tick_upper,
+        -i128::try_from(liquidity)?,
+    )?;
+    Ok(PositionQuote {
+        tick_range,
+        liquidity,
+        amount_0,
+        amount_1,
+        transfer_fee_0: transfer_fee(transfer_fees.token_0.as_ref(), amount_0)?,
+        transfer_fee_1: transfer_fee(transfer_fees.token_1.as_ref(), amount_1)?,
+    })
+}
+
+/// The side of a swap whose amount is fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapAmount {
+    /// Spend exactly this much of the input token, transfer fee included.
+    ExactIn(u64),
+    /// Receive exactly this much of the output token, after transfer fees.
+    ExactOut(u64),
+}
+
+impl SwapAmount {
+    pub fn is_base_input(&self) -> bool {
+        matches!(self, SwapAmount::ExactIn(_))
+    }
+
+    pub fn amount(&self) -> u64 {
+        match *self {
+            SwapAmount::ExactIn(amount) | SwapAmount::ExactOut(amount) => amount,
+        }
+    }
+}
+
+/// Result of quoting a CLMM swap. `amount_in` leaves the wallet and
+/// `amount_out` arrives in it, so both account for transfer fees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClmmSwapQuote {
+    pub zero_for_one: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub trade_fee: u64,
+    pub transfer_fee_in: u64,
+    pub transfer_fee_out: u64,
+    pub sqrt_price_after_x64: u128,
+    pub tick_after: i32,
+    pub tick_arrays_touched: Vec<i32>,
+}
+
+/// Checks that `sqrt_price_limit_x64` lies strictly between the current
+/// price and the end of the price range in the swap direction.
+pub fn validate_sqrt_price_limit(
+    sqrt_price_x64: u128,
+    sqrt_price_limit_x64: u128,
+    zero_for_one: bool,
+) -> Result<()> {
+    let valid = if zero_for_one {
+        sqrt_price_limit_x64 < sqrt_price_x64 && sqrt_price_limit_x64 > MIN_SQRT_PRICE_X64
+    } else {
+        sqrt_price_limit_x64 > sqrt_price_x64 && sqrt_price_limit_x64 < MAX_SQRT_PRICE_X64
+    };
+    if !valid {
+        return Err(format_err!(
+            "sqrt price limit {} is on the wrong side of the current sqrt price {}",
+            sqrt_price_limit_x64,
+            sqrt_price_x64
+        ));
+    }
+    Ok(())
+}
+
+/// The loosest limit the program accepts in the swap direction.
+pub fn default_sqrt_price_limit(zero_for_one: bool) -> u128 {
+    if zero_for_one {
+        MIN_SQRT_PRICE_X64 + 1
+    } else {
+        MAX_SQRT_PRICE_X64 - 1
+    }
+}
+
+/// Quotes a swap by simulating it across `tick_arrays`, the pool's
+/// initialised tick arrays in the swap direction. Only full fills are
+/// quoted: a swap the price limit would cut short is an error.
+pub fn quote_swap(
+    pool_state: &PoolState,
+    amm_config: &AmmConfig,
+    tick_arrays: &[TickArrayState],
+    transfer_fees: &PoolTransferFees,
+    zero_for_one: bool,
+    amount: SwapAmount,
+    sqrt_price_limit_x64: u128,
+) -> Result<ClmmSwapQuote> {
+    validate_sqrt_price_limit(
+        pool_state.sqrt_price_x64,
+        sqrt_price_limit_x64,
+        zero_for_one,
+    )?;
+    let (fee_in, fee_out) = if zero_for_one {
+        (
+            transfer_fees.token_0.as_ref(),
+            transfer_fees.token_1.as_ref(),
+        )
+    } else {
+        (
+            transfer_fees.token_1.as_ref(),
+            transfer_fees.token_0.as_ref(),
+        )
+    };
+    let simulate = |amount_specified| {
+        simulate_swap(
+            pool_state,
+            amm_config,
+            tick_arrays,
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit_x64,
+            amount.is_base_input(),
+        )
+    };
+    let past_limit = || format_err!("Swap would move the price past its limit");
+
+    let (amount_in, amount_out, transfer_fee_in, transfer_fee_out, simulation) = match amount {
+        SwapAmount::ExactIn(amount_in) => {
+            let transfer_fee_in = transfer_fee(fee_in, amount_in)?;
+            let simulation = simulate(amount_in - transfer_fee_in)?;
+            if simulation.amount_remaining != 0 {
+                return Err(past_limit());
+            }
+            let transfer_fee_out = transfer_fee(fee_out, simulation.amount_out)?;
+            (
+                amount_in,
+                simulation.amount_out - transfer_fee_out,
+                transfer_fee_in,
+                transfer_fee_out,
+                simulation,
+            )
+        }
+        SwapAmount::ExactOut(amount_out) => {
+            let transfer_fee_out = transfer_inverse_fee(fee_out, amount_out)?;
+            let simulation = simulate(
+                amount_out
+                    .checked_add(transfer_fee_out)
+                    .ok_or_else(|| format_err!("Output amount overflows"))?,
+            )?;
+            if simulation.amount_remaining != 0 {
+                return Err(past_limit());
+            }
+            let transfer_fee_in = transfer_inverse_fee(fee_in, simulation.amount_in)?;
+            (
+                simulation
+                    .amount_in
+                    .checked_add(transfer_fee_in)
+                    .ok_or_else(|| format_err!("Input amount overflows"))?,
+                amount_out,
+                transfer_fee_in,
+                transfer_fee_out,
+                simulation,
+            )
+        }
+    };
+
+    Ok(ClmmSwapQuote {
+        zero_for_one,
+        amount_in,
+        amount_out,
+        trade_fee: simulation.fee_amount,
+        transfer_fee_in,
+        transfer_fee_out,
+        sqrt_price_after_x64: simulation.sqrt_price_x64,
+        tick_after: simulation.tick_current,
+        tick_arrays_touched: simulation.tick_arrays_touched,
+    })
+}
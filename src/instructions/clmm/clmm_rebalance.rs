@@ -0,0 +1,682 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    message::AddressLookupTableAccount,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    sysvar,
+    transaction::VersionedTransaction,
+};
+use solana_system_interface::program as system_program;
+use spl_token_2022::extension::transfer_fee::TransferFee;
+
+use super::clmm_config::fetch_amm_config;
+use super::clmm_decrease_liquidity::decrease_liquidity_instructions;
+use super::clmm_open_position::OpenedPosition;
+use super::clmm_pool::{ClmmPool, PoolState};
+use super::clmm_position::{ClmmPosition, personal_position_address};
+use super::clmm_position_fees::fetch_position_accrual;
+use super::clmm_quote::{
+    ClmmSwapQuote, SwapAmount, default_sqrt_price_limit, quote_decrease_liquidity,
+    quote_position_from_amounts, quote_swap,
+};
+use super::clmm_swap::SWAP_TICK_ARRAY_COUNT;
+use super::clmm_tick_array::{
+    TickArrayState, fetch_tick_array_bitmap_extension, fetch_tick_arrays,
+    swap_tick_array_start_indexes, tick_array_address, tick_array_bitmap_extension_address,
+};
+use super::liquidity_math::{add_delta, get_delta_amounts_signed};
+use super::tick_math::{MAX_TICK, MIN_TICK, TickRange, floor_tick_to_spacing};
+use crate::instructions::cp_amm::cp_amm_transfer_fee::{PoolTransferFees, transfer_fee};
+use crate::utils::slippage::Slippage;
+use crate::utils::transaction_utils::{
+    compile_message, create_lookup_table, fetch_lookup_table, fits_in_packet,
+    signed_transaction_size,
+};
+
+/// Decreasing, closing, swapping and opening in one transaction needs more
+/// than the default compute budget, so ask for the most a transaction gets.
+const REBALANCE_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// How a position is kept around the current price.
+#[derive(Debug, Clone, Copy)]
+pub struct RebalanceConfig {
+    /// Width of each new position in ticks, rounded up to the tick spacing.
+    pub width_ticks: i32,
+    /// Rebalance once the current tick is this close to either edge, not
+    /// only once it leaves the range.
+    pub edge_ticks: i32,
+    /// Swap the withdrawn tokens to the new range's ratio before reopening.
+    pub swap_to_ratio: bool,
+    pub slippage: Slippage,
+    /// Log the planned transaction instead of sending it.
+    pub dry_run: bool,
+    /// Lookup table to compress the rebalance with when it is too large for
+    /// a legacy transaction. One is created when needed and none is given.
+    pub lookup_table: Option<Pubkey>,
+}
+
+/// What a rebalance sent, in order, and the position it opened.
+#[derive(Debug, Clone)]
+pub struct Rebalance {
+    pub signatures: Vec<Signature>,
+    pub opened: OpenedPosition,
+    /// The lookup table the rebalance used, to reuse on the next one.
+    pub lookup_table: Option<Pubkey>,
+}
+
+/// Whether `tick_current` is outside `tick_range`, or within `edge_ticks` of
+/// either end of it.
+pub fn needs_rebalance(tick_range: TickRange, tick_current: i32, edge_ticks: i32) -> bool {
+    tick_current < tick_range.tick_lower.saturating_add(edge_ticks)
+        || tick_current >= tick_range.tick_upper.saturating_sub(edge_ticks)
+}
+
+/// A range `width_ticks` wide, rounded up to whole tick spacings, with
+/// `tick_current` as close to its middle as the spacing and the usable
+/// ticks allow.
+pub fn centred_tick_range(
+    tick_current: i32,
+    tick_spacing: u16,
+    width_ticks: i32,
+) -> Result<TickRange> {
+    let spacing = i32::from(tick_spacing);
+    if width_ticks <= 0 {
+        return Err(format_err!(
+            "Range width must be positive, got {}",
+            width_ticks
+        ));
+    }
+    let width = (width_ticks + spacing - 1) / spacing * spacing;
+    let max_tick_lower = MAX_TICK / spacing * spacing - width;
+    let tick_lower =
+        floor_tick_to_spacing(tick_current - width / 2, tick_spacing).min(max_tick_lower);
+    let tick_range = TickRange {
+        tick_lower,
+        tick_upper: tick_lower + width,
+    };
+    if tick_range.tick_lower < MIN_TICK || tick_range.tick_upper > MAX_TICK {
+        return Err(format_err!(
+            "Range [{}, {}) runs past the usable ticks",
+            tick_range.tick_lower,
+            tick_range.tick_upper
+        ));
+    }
+    Ok(tick_range)
+}
+
+/// The swap that brings `amount_0` and `amount_1` to the ratio a position
+/// over `tick_range` takes at the pool's current price, as whether it is
+/// token 0 in and the exact input. Fees and price impact are ignored, so the
+/// result is close to, not exactly at, the ratio.
+pub fn rebalance_swap(
+    pool_state: &PoolState,
+    tick_range: TickRange,
+    amount_0: u64,
+    amount_1: u64,
+) -> Result<Option<(bool, u64)>> {
+    // Amounts for a nominal liquidity only set the ratio.
+    let (ratio_0, ratio_1) = get_delta_amounts_signed(
+        pool_state.tick_current,
+        pool_state.sqrt_price_x64,
+        tick_range.tick_lower,
+        tick_range.tick_upper,
+        1 << 64,
+    )?;
+    let sqrt_price = pool_state.sqrt_price_x64 as f64 / 2f64.powi(64);
+    let price = sqrt_price * sqrt_price;
+
+    let target_value = ratio_0 as f64 * price + ratio_1 as f64;
+    if target_value == 0.0 || price == 0.0 {
+        return Ok(None);
+    }
+    let value = amount_0 as f64 * price + amount_1 as f64;
+    let target_1 = value * ratio_1 as f64 / target_value;
+
+    let swap = if (amount_1 as f64) < target_1 {
+        (true, ((target_1 - amount_1 as f64) / price) as u64)
+    } else {
+        (false, (amount_1 as f64 - target_1) as u64)
+    };
+    Ok(Some(swap).filter(|(_, amount_in)| *amount_in > 0))
+}
+
+fn token_balance(rpc_client: &RpcClient, token_account: &Pubkey) -> Result<u64> {
+    let balance = rpc_client
+        .get_token_account_balance(token_account)
+        .map_err(|err| format_err!("Failed to read token account {}: {}", token_account, err))?;
+    Ok(balance.amount.parse()?)
+}
+
+/// The owner's token 0 and token 1 balances.
+fn pool_balances(rpc_client: &RpcClient, pool: &ClmmPool) -> Result<(u64, u64)> {
+    Ok((
+        token_balance(rpc_client, &pool.owner_token_0_ata)?,
+        token_balance(rpc_client, &pool.owner_token_1_ata)?,
+    ))
+}
+
+/// What arrives when `amount` and `fees` are paid out in one transfer.
+fn received_amount(fee: Option<&TransferFee>, amount: u64, fees: u64) -> Result<u64> {
+    let gross = amount
+        .checked_add(fees)
+        .ok_or_else(|| format_err!("Withdrawn amount overflows"))?;
+    Ok(gross - transfer_fee(fee, gross)?)
+}
+
+/// Takes `position`'s liquidity out of `pool_state` and `tick_arrays`, as
+/// decreasing it in full does on chain, and drops tick arrays left with no
+/// initialised ticks, which a swap no longer walks through.
+fn remove_position(
+    pool_state: &mut PoolState,
+    tick_arrays: &mut Vec<(i32, TickArrayState)>,
+    tick_range: TickRange,
+    liquidity: u128,
+) -> Result<()> {
+    if tick_range.tick_lower <= pool_state.tick_current
+        && pool_state.tick_current < tick_range.tick_upper
+    {
+        pool_state.liquidity = add_delta(pool_state.liquidity, -i128::try_from(liquidity)?)?;
+    }
+    for (tick_index, liquidity_net_delta) in [
+        (tick_range.tick_lower, -i128::try_from(liquidity)?),
+        (tick_range.tick_upper, i128::try_from(liquidity)?),
+    ] {
+        for (_, tick_array) in tick_arrays.iter_mut() {
+            let Ok(tick) = tick_array.tick_mut(tick_index, pool_state.tick_spacing) else {
+                continue;
+            };
+            tick.liquidity_gross = add_delta(tick.liquidity_gross, -i128::try_from(liquidity)?)?;
+            tick.liquidity_net = if tick.liquidity_gross == 0 {
+                0
+            } else {
+                tick.liquidity_net + liquidity_net_delta
+            };
+        }
+    }
+    tick_arrays.retain(|(_, tick_array)| tick_array.ticks.iter().any(|tick| tick.is_initialized()));
+    Ok(())
+}
+
+/// Quotes the exact-in swap against the pool as it will be once `position`
+/// is closed, and builds the instruction for it. `pool_state` is moved to
+/// the price the swap leaves behind.
+fn plan_swap(
+    rpc_client: &RpcClient,
+    pool: &ClmmPool,
+    pool_state: &mut PoolState,
+    position: &ClmmPosition,
+    transfer_fees: &PoolTransferFees,
+    (zero_for_one, amount_in): (bool, u64),
+    slippage: Slippage,
+) -> Result<(Instruction, ClmmSwapQuote, u64)> {
+    let amm_config = fetch_amm_config(rpc_client, &pool.state.amm_config)?;
+    let bitmap_extension =
+        fetch_tick_array_bitmap_extension(rpc_client, &pool.program_id, &pool.pool_id)?;
+    let start_indexes = swap_tick_array_start_indexes(
+        &pool.state,
+        &bitmap_extension,
+        zero_for_one,
+        SWAP_TICK_ARRAY_COUNT,
+    );
+    let mut tick_arrays: Vec<_> = start_indexes
+        .iter()
+        .copied()
+        .zip(fetch_tick_arrays(
+            rpc_client,
+            &pool.program_id,
+            &pool.pool_id,
+            &start_indexes,
+        )?)
+        .collect();
+    remove_position(
+        pool_state,
+        &mut tick_arrays,
+        position.tick_range(),
+        position.state.liquidity,
+    )?;
+    if tick_arrays.is_empty() {
+        return Err(format_err!(
+            "Pool {} has no initialised tick arrays in the swap direction",
+            pool.pool_id
+        ));
+    }
+
+    let sqrt_price_limit_x64 = default_sqrt_price_limit(zero_for_one);
+    let tick_array_states: Vec<_> = tick_arrays
+        .iter()
+        .map(|(_, tick_array)| tick_array.clone())
+        .collect();
+    let quote = quote_swap(
+        pool_state,
+        &amm_config,
+        &tick_array_states,
+        transfer_fees,
+        zero_for_one,
+        SwapAmount::ExactIn(amount_in),
+        sqrt_price_limit_x64,
+    )?;
+    let minimum_amount_out = slippage.min_amount(quote.amount_out)?;
+
+    let tick_array_addresses: Vec<_> = tick_arrays
+        .iter()
+        .map(|(start_index, _)| tick_array_address(&pool.program_id, &pool.pool_id, *start_index))
+        .collect();
+    let instruction = pool.swap_instruction(
+        zero_for_one,
+        amount_in,
+        minimum_amount_out,
+        sqrt_price_limit_x64,
+        true,
+        &tick_array_addresses,
+    )?;
+
+    pool_state.sqrt_price_x64 = quote.sqrt_price_after_x64;
+    pool_state.tick_current = quote.tick_after;
+    Ok((instruction, quote, minimum_amount_out))
+}
+
+/// Accounts every rebalance on `pool` touches, for a lookup table that can
+/// be reused from one rebalance to the next.
+fn lookup_table_addresses(rpc_client: &RpcClient, pool: &ClmmPool) -> Result<Vec<Pubkey>> {
+    let mut addresses = vec![
+        pool.pool_id,
+        pool.state.amm_config,
+        pool.state.observation_key,
+        pool.state.token_vault_0,
+        pool.state.token_vault_1,
+        pool.state.token_mint_0,
+        pool.state.token_mint_1,
+        pool.owner_token_0_ata,
+        pool.owner_token_1_ata,
+        tick_array_bitmap_extension_address(&pool.program_id, &pool.pool_id),
+        spl_token::id(),
+        spl_token_2022::id(),
+        spl_memo::id(),
+        spl_associated_token_account::id(),
+        system_program::id(),
+        sysvar::rent::id(),
+    ];
+    for reward in pool.fetch_reward_accounts(rpc_client)? {
+        addresses.extend([reward.reward_vault, reward.recipient, reward.reward_mint]);
+    }
+    addresses.sort();
+    addresses.dedup();
+    Ok(addresses)
+}
+
+/// Checks `position` against the current price and, if it needs it, moves
+/// it to a range around the current price in one transaction: its liquidity
+/// and fees are withdrawn, the old position is closed, the proceeds are
+/// optionally swapped to the new range's ratio, and a new position is opened
+/// with them. A v0 transaction and lookup table are used when a legacy one
+/// is too large. Returns `None` when nothing was sent.
+pub fn clmm_rebalance_once(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    position: &ClmmPosition,
+    config: &RebalanceConfig,
+) -> Result<Option<Rebalance>> {
+    pool.ensure_owner(&user.pubkey())?;
+    position.ensure_pool(&pool.pool_id)?;
+    let slippage = config.slippage;
+    slippage.validate()?;
+
+    let transfer_fees = pool.refresh(rpc_client)?;
+    let tick_range = position.tick_range();
+    let tick_current = pool.state.tick_current;
+    if !needs_rebalance(tick_range, tick_current, config.edge_ticks) {
+        println!(
+            "Tick {} is inside [{}, {}) with room to spare",
+            tick_current, tick_range.tick_lower, tick_range.tick_upper
+        );
+        return Ok(None);
+    }
+    let new_range = centred_tick_range(tick_current, pool.state.tick_spacing, config.width_ticks)?;
+    println!(
+        "Tick {} is at or past the edge of [{}, {}), moving position {} to [{}, {})",
+        tick_current,
+        tick_range.tick_lower,
+        tick_range.tick_upper,
+        position.nft_mint,
+        new_range.tick_lower,
+        new_range.tick_upper
+    );
+
+    let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        REBALANCE_COMPUTE_UNIT_LIMIT,
+    )];
+    instructions.extend(decrease_liquidity_instructions(
+        rpc_client, pool, position, None, slippage,
+    )?);
+    instructions.push(position.close_position_instruction(&user.pubkey())?);
+
+    // What closing should return: the liquidity and the fees owed, paid out
+    // together, so one transfer fee applies to both.
+    let decrease = quote_decrease_liquidity(
+        &pool.state,
+        tick_range,
+        position.state.liquidity,
+        &transfer_fees,
+    )?;
+    let accrual = fetch_position_accrual(
+        rpc_client,
+        &pool.program_id,
+        &pool.pool_id,
+        &pool.state,
+        &position.state,
+    )?;
+    let expected_0 = received_amount(
+        transfer_fees.token_0.as_ref(),
+        decrease.amount_0,
+        accrual.fees_owed_0,
+    )?;
+    let expected_1 = received_amount(
+        transfer_fees.token_1.as_ref(),
+        decrease.amount_1,
+        accrual.fees_owed_1,
+    )?;
+    // A centred range takes both tokens, which an out of range position
+    // only returns one of. Stop before sending anything.
+    if !config.swap_to_ratio && (expected_0 == 0 || expected_1 == 0) {
+        return Err(format_err!(
+            "Position {} would return only one token, so a range around the current price cannot be funded without swapping",
+            position.nft_mint
+        ));
+    }
+
+    // Fund the new position from what closing should return. The wallet's
+    // own balance may cover a shortfall down to the slippage bound, but the
+    // new position never takes more than that.
+    let (balance_0, balance_1) = pool_balances(rpc_client, pool)?;
+    let budget = |expected: u64, balance: u64| -> Result<u64> {
+        Ok(expected.min(balance.saturating_add(slippage.min_amount(expected)?)))
+    };
+    let mut budget_0 = budget(expected_0, balance_0)?;
+    let mut budget_1 = budget(expected_1, balance_1)?;
+    println!(
+        "Expected from closing: {} token 0 and {} token 1 with fees",
+        expected_0, expected_1
+    );
+
+    let mut pool_state = pool.state.clone();
+    if config.swap_to_ratio
+        && let Some((zero_for_one, amount_in)) =
+            rebalance_swap(&pool_state, new_range, budget_0, budget_1)?
+    {
+        let (instruction, quote, minimum_amount_out) = plan_swap(
+            rpc_client,
+            pool,
+            &mut pool_state,
+            position,
+            &transfer_fees,
+            (zero_for_one, amount_in),
+            slippage,
+        )?;
+        instructions.push(instruction);
+        println!(
+            "Swap {} token {} for {} token {}, Min Accepted: {}",
+            quote.amount_in,
+            if zero_for_one { 0 } else { 1 },
+            quote.amount_out,
+            if zero_for_one { 1 } else { 0 },
+            minimum_amount_out
+        );
+        let (budget_in, budget_out) = if zero_for_one {
+            (&mut budget_0, &mut budget_1)
+        } else {
+            (&mut budget_1, &mut budget_0)
+        };
+        *budget_in = budget_in
+            .checked_sub(quote.amount_in)
+            .ok_or_else(|| format_err!("Swap input exceeds the withdrawn amount"))?;
+        *budget_out = budget_out.saturating_add(minimum_amount_out);
+    }
+
+    let open = quote_position_from_amounts(
+        &pool_state,
+        new_range,
+        Some(slippage.quote_within(budget_0)?),
+        Some(slippage.quote_within(budget_1)?),
+        &transfer_fees,
+    )?;
+    let (amount_0_to_deposit, amount_1_to_deposit) = open.deposit_amounts()?;
+    let amount_0_max = slippage.max_amount_capped(amount_0_to_deposit, Some(budget_0))?;
+    let amount_1_max = slippage.max_amount_capped(amount_1_to_deposit, Some(budget_1))?;
+    println!(
+        "Open [{}, {}) with liquidity {}, Max Allowed: {} token 0 and {} token 1",
+        new_range.tick_lower, new_range.tick_upper, open.liquidity, amount_0_max, amount_1_max
+    );
+
+    let position_nft_mint = Keypair::new();
+    instructions.push(pool.open_position_instruction(
+        &position_nft_mint.pubkey(),
+        open.tick_range,
+        open.liquidity,
+        amount_0_max,
+        amount_1_max,
+    )?);
+
+    let mut signatures = Vec::new();
+    let mut latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let mut message = compile_message(&user.pubkey(), &instructions, &[], latest_blockhash)?;
+    let mut lookup_table = None;
+    if !fits_in_packet(&message) {
+        let table = match config.lookup_table {
+            Some(address) => fetch_lookup_table(rpc_client, &address)?,
+            // Only the size matters for a dry run, so no table is created.
+            None if config.dry_run => AddressLookupTableAccount {
+                key: Pubkey::default(),
+                addresses: lookup_table_addresses(rpc_client, pool)?,
+            },
+            None => {
+                let (table, table_signatures) = create_lookup_table(
+                    rpc_client,
+                    user,
+                    &lookup_table_addresses(rpc_client, pool)?,
+                )?;
+                signatures.extend(table_signatures);
+                latest_blockhash = rpc_client.get_latest_blockhash()?;
+                table
+            }
+        };
+        message = compile_message(
+            &user.pubkey(),
+            &instructions,
+            std::slice::from_ref(&table),
+            latest_blockhash,
+        )?;
+        if !fits_in_packet(&message) {
+            return Err(format_err!(
+                "Rebalance transaction is {} bytes even with a lookup table",
+                signed_transaction_size(&message)
+            ));
+        }
+        lookup_table = Some(table.key);
+    }
+
+    if config.dry_run {
+        println!(
+            "[dry run] One {} transaction of {} bytes",
+            if lookup_table.is_some() {
+                "v0"
+            } else {
+                "legacy"
+            },
+            signed_transaction_size(&message)
+        );
+        return Ok(None);
+    }
+
+    let transaction = VersionedTransaction::try_new(message, &[user, &position_nft_mint])?;
+    println!("Sending rebalance transaction...");
+    signatures.push(rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?);
+
+    Ok(Some(Rebalance {
+        signatures,
+        opened: OpenedPosition {
+            position_nft_mint: position_nft_mint.pubkey(),
+            personal_position: personal_position_address(
+                &pool.program_id,
+                &position_nft_mint.pubkey(),
+            ),
+            tick_range: open.tick_range,
+            liquidity: open.liquidity,
+        },
+        lookup_table,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+
+    fn range(tick_lower: i32, tick_upper: i32) -> TickRange {
+        TickRange {
+            tick_lower,
+            tick_upper,
+        }
+    }
+
+    fn pool_state(tick_current: i32, liquidity: u128) -> PoolState {
+        let mut pool_state = PoolState::deserialize(&mut &[0u8; 1536][..]).unwrap();
+        pool_state.tick_spacing = 10;
+        pool_state.tick_current = tick_current;
+        pool_state.liquidity = liquidity;
+        pool_state
+    }
+
+    /// A tick array holding `ticks` as `(tick, liquidity_gross, liquidity_net)`.
+    fn tick_array(start_tick_index: i32, ticks: &[(i32, u128, i128)]) -> (i32, TickArrayState) {
+        let mut tick_array = TickArrayState::deserialize(&mut &[0u8; 10232][..]).unwrap();
+        tick_array.start_tick_index = start_tick_index;
+        for &(tick_index, liquidity_gross, liquidity_net) in ticks {
+            let tick = tick_array.tick_mut(tick_index, 10).unwrap();
+            tick.tick = tick_index;
+            tick.liquidity_gross = liquidity_gross;
+            tick.liquidity_net = liquidity_net;
+        }
+        (start_tick_index, tick_array)
+    }
+
+    fn liquidity_at(tick_arrays: &[(i32, TickArrayState)], tick_index: i32) -> (u128, i128) {
+        let tick = tick_arrays
+            .iter()
+            .find_map(|(_, tick_array)| tick_array.tick(tick_index, 10).ok())
+            .unwrap();
+        (tick.liquidity_gross, tick.liquidity_net)
+    }
+
+    #[test]
+    fn rebalances_outside_the_range() {
+        let tick_range = range(-100, 100);
+        assert!(needs_rebalance(tick_range, -101, 0));
+        assert!(!needs_rebalance(tick_range, -100, 0));
+        assert!(!needs_rebalance(tick_range, 99, 0));
+        assert!(needs_rebalance(tick_range, 100, 0));
+    }
+
+    #[test]
+    fn rebalances_within_the_edge() {
+        let tick_range = range(-100, 100);
+        assert!(needs_rebalance(tick_range, -91, 10));
+        assert!(!needs_rebalance(tick_range, -90, 10));
+        assert!(!needs_rebalance(tick_range, 89, 10));
+        assert!(needs_rebalance(tick_range, 90, 10));
+        // An edge wider than half the range always rebalances.
+        assert!(needs_rebalance(tick_range, 0, 100));
+    }
+
+    #[test]
+    fn centres_the_range_on_the_price() {
+        assert_eq!(centred_tick_range(0, 10, 200).unwrap(), range(-100, 100));
+        assert_eq!(centred_tick_range(5, 10, 200).unwrap(), range(-100, 100));
+        assert_eq!(centred_tick_range(15, 10, 200).unwrap(), range(-90, 110));
+        assert_eq!(centred_tick_range(-95, 10, 200).unwrap(), range(-200, 0));
+        assert_eq!(centred_tick_range(-105, 10, 200).unwrap(), range(-210, -10));
+    }
+
+    #[test]
+    fn rounds_the_width_up_to_the_spacing() {
+        assert_eq!(centred_tick_range(0, 60, 1).unwrap(), range(-60, 0));
+        assert_eq!(centred_tick_range(0, 60, 100).unwrap(), range(-60, 60));
+        assert_eq!(centred_tick_range(0, 60, 120).unwrap(), range(-60, 60));
+        assert_eq!(centred_tick_range(-1, 60, 121).unwrap(), range(-120, 60));
+        assert!(centred_tick_range(0, 60, 0).is_err());
+        assert!(centred_tick_range(0, 60, -60).is_err());
+    }
+
+    #[test]
+    fn keeps_the_range_within_the_usable_ticks() {
+        // MIN_TICK and MAX_TICK are ±443636, so ±443630 are the last usable.
+        assert_eq!(
+            centred_tick_range(MAX_TICK - 10, 10, 200).unwrap(),
+            range(443430, 443630)
+        );
+        assert_eq!(
+            centred_tick_range(MIN_TICK + 10, 10, 200).unwrap(),
+            range(-443630, -443430)
+        );
+        assert!(centred_tick_range(0, 10, 2 * MAX_TICK).is_err());
+    }
+
+    #[test]
+    fn removing_a_position_undoes_its_liquidity() {
+        // The position [-100, 100) holds 400, another [-50, 50) holds 600.
+        let mut pool_state = pool_state(0, 1000);
+        let mut tick_arrays = vec![
+            tick_array(-600, &[(-100, 400, 400), (-50, 600, 600)]),
+            tick_array(0, &[(50, 600, -600), (100, 400, -400)]),
+        ];
+        remove_position(&mut pool_state, &mut tick_arrays, range(-100, 100), 400).unwrap();
+
+        assert_eq!(pool_state.liquidity, 600);
+        assert_eq!(liquidity_at(&tick_arrays, -100), (0, 0));
+        assert_eq!(liquidity_at(&tick_arrays, 100), (0, 0));
+        assert_eq!(liquidity_at(&tick_arrays, -50), (600, 600));
+        assert_eq!(liquidity_at(&tick_arrays, 50), (600, -600));
+        assert_eq!(tick_arrays.len(), 2);
+    }
+
+    #[test]
+    fn removing_a_position_keeps_shared_ticks() {
+        // [-100, 100) holds 400 and [100, 200) holds 300, sharing tick 100.
+        let mut pool_state = pool_state(0, 400);
+        let mut tick_arrays = vec![
+            tick_array(-600, &[(-100, 400, 400)]),
+            tick_array(0, &[(100, 700, -100), (200, 300, -300)]),
+        ];
+        remove_position(&mut pool_state, &mut tick_arrays, range(-100, 100), 400).unwrap();
+
+        assert_eq!(pool_state.liquidity, 0);
+        // Tick 100 is now only the lower tick of [100, 200).
+        assert_eq!(liquidity_at(&tick_arrays, 100), (300, 300));
+        assert_eq!(liquidity_at(&tick_arrays, 200), (300, -300));
+        // The array that only held this position's lower tick is dropped.
+        assert_eq!(
+            tick_arrays
+                .iter()
+                .map(|(start_index, _)| *start_index)
+                .collect::<Vec<_>>(),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn removing_an_out_of_range_position_keeps_pool_liquidity() {
+        let mut pool_state = pool_state(150, 300);
+        let mut tick_arrays = vec![
+            tick_array(-600, &[(-100, 400, 400)]),
+            tick_array(0, &[(100, 700, -100), (200, 300, -300)]),
+        ];
+        remove_position(&mut pool_state, &mut tick_arrays, range(-100, 100), 400).unwrap();
+        assert_eq!(pool_state.liquidity, 300);
+
+        // Removing more than a tick holds is an error, not a wrap.
+        assert!(remove_position(&mut pool_state, &mut tick_arrays, range(100, 200), 400).is_err());
+    }
+}
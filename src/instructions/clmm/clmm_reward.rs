@@ -0,0 +1,442 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::extension::transfer_fee::TransferFee;
+use spl_token_2022::state::{Account as TokenAccount, AccountState};
+
+use super::big_num::U256;
+use super::clmm_pool::RewardInfo;
+use crate::instructions::cp_amm::cp_amm_transfer_fee::{epoch_transfer_fee, transfer_inverse_fee};
+
+pub const OPERATION_SEED: &[u8] = b"operation";
+pub const POOL_REWARD_VAULT_SEED: &[u8] = b"pool_reward_vault";
+
+/// Shortest and longest reward period the program accepts, in seconds.
+pub const MIN_REWARD_PERIOD: u64 = 7 * 24 * 60 * 60;
+pub const MAX_REWARD_PERIOD: u64 = 90 * 24 * 60 * 60;
+
+/// A running reward can only be extended or topped up this close to its
+/// end, in seconds.
+pub const REWARD_EXTENSION_WINDOW: u64 = 72 * 60 * 60;
+
+const Q64: u128 = 1 << 64;
+
+/// The program's `OperationState`, which holds the reward mint whitelist.
+pub fn operation_state_address(program_id: &Pubkey) -> Pubkey {
+    let (operation_state, _) = Pubkey::find_program_address(&[OPERATION_SEED], program_id);
+    operation_state
+}
+
+/// The vault a pool pays a reward stream out of.
+pub fn reward_vault_address(program_id: &Pubkey, pool_id: &Pubkey, reward_mint: &Pubkey) -> Pubkey {
+    let (reward_vault, _) = Pubkey::find_program_address(
+        &[
+            POOL_REWARD_VAULT_SEED,
+            pool_id.as_ref(),
+            reward_mint.as_ref(),
+        ],
+        program_id,
+    );
+    reward_vault
+}
+
+/// Unix time of the cluster's latest slot, which is what the program
+/// compares reward schedules against.
+pub fn fetch_cluster_time(rpc_client: &RpcClient) -> Result<u64> {
+    let slot = rpc_client.get_slot()?;
+    u64::try_from(rpc_client.get_block_time(slot)?)
+        .map_err(|_| format_err!("Cluster time is before the epoch"))
+}
+
+/// A reward stream's emission schedule. Emissions are in reward token base
+/// units per second, as Q64.64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RewardSchedule {
+    pub open_time: u64,
+    pub end_time: u64,
+    pub emissions_per_second_x64: u128,
+}
+
+impl RewardSchedule {
+    /// Spreads `total_amount` evenly over `[open_time, end_time)`, rounding
+    /// the emission rate down so the vault is never short.
+    pub fn from_total_amount(open_time: u64, end_time: u64, total_amount: u64) -> Result<Self> {
+        let duration = end_time
+            .checked_sub(open_time)
+            .filter(|duration| *duration > 0)
+            .ok_or_else(|| format_err!("Reward must end after it opens"))?;
+        let emissions_per_second_x64 = U256::from(total_amount)
+            .mul_div_floor(U256::from(Q64), U256::from(duration))
+            .ok_or_else(|| format_err!("Emission rate overflow"))?;
+        Ok(Self {
+            open_time,
+            end_time,
+            emissions_per_second_x64: emissions_per_second_x64.as_u128(),
+        })
+    }
+
+    /// Checks a new reward period the way the program does at `now`: it must
+    /// start after `now`, must emit something and must last between
+    /// `MIN_REWARD_PERIOD` and `MAX_REWARD_PERIOD`.
+    pub fn validate(&self, now: u64) -> Result<()> {
+        if self.open_time <= now {
+            return Err(format_err!(
+                "Reward open time {} is not after the cluster time {}",
+                self.open_time,
+                now
+            ));
+        }
+        if self.end_time <= self.open_time {
+            return Err(format_err!(
+                "Reward end time {} must be after its open time {}",
+                self.end_time,
+                self.open_time
+            ));
+        }
+        if self.emissions_per_second_x64 == 0 {
+            return Err(format_err!("Reward emissions per second must be positive"));
+        }
+        validate_reward_period(self.end_time - self.open_time)
+    }
+
+    /// Tokens the vault needs to pay out the whole schedule, rounded up as
+    /// the program does.
+    pub fn total_amount(&self) -> Result<u64> {
+        emitted_amount(
+            self.end_time.saturating_sub(self.open_time),
+            self.emissions_per_second_x64,
+        )
+    }
+}
+
+fn validate_reward_period(period: u64) -> Result<()> {
+    if !(MIN_REWARD_PERIOD..=MAX_REWARD_PERIOD).contains(&period) {
+        return Err(format_err!(
+            "Reward period of {}s must be between {}s and {}s",
+            period,
+            MIN_REWARD_PERIOD,
+            MAX_REWARD_PERIOD
+        ));
+    }
+    Ok(())
+}
+
+/// Tokens emitted over `seconds` at `emissions_per_second_x64`, rounded up.
+fn emitted_amount(seconds: u64, emissions_per_second_x64: u128) -> Result<u64> {
+    let amount = U256::from(seconds)
+        .mul_div_ceil(U256::from(emissions_per_second_x64), U256::from(Q64))
+        .ok_or_else(|| format_err!("Reward amount overflow"))?;
+    u64::try_from(amount.as_u128()).map_err(|_| format_err!("Reward amount exceeds u64"))
+}
+
+/// Validates moving `reward_info` to `schedule` at `now` through
+/// `set_reward_params`, returning the tokens the reward authority must add
+/// to the vault.
+///
+/// A reward that has ended can be restarted with a fresh schedule. A running
+/// one can only be extended, by `MIN_REWARD_PERIOD` to `MAX_REWARD_PERIOD`,
+/// within `REWARD_EXTENSION_WINDOW` of its end, and its emissions can only
+/// go up; its open time is kept.
+pub fn reward_update_amount(
+    reward_info: &RewardInfo,
+    schedule: &RewardSchedule,
+    now: u64,
+) -> Result<u64> {
+    if now >= reward_info.end_time {
+        schedule.validate(now)?;
+        return schedule.total_amount();
+    }
+
+    let time_left = reward_info.end_time - now;
+    if time_left > REWARD_EXTENSION_WINDOW {
+        return Err(format_err!(
+            "Reward {} runs for another {}s and can only be changed in its last {}s",
+            reward_info.token_mint,
+            time_left,
+            REWARD_EXTENSION_WINDOW
+        ));
+    }
+    let extension = schedule
+        .end_time
+        .checked_sub(reward_info.end_time)
+        .ok_or_else(|| {
+            format_err!(
+                "A running reward cannot end earlier than {}",
+                reward_info.end_time
+            )
+        })?;
+    validate_reward_period(extension)?;
+    let emissions_increase = schedule
+        .emissions_per_second_x64
+        .checked_sub(reward_info.emissions_per_second_x64)
+        .ok_or_else(|| format_err!("A running reward's emissions cannot be lowered"))?;
+
+    emitted_amount(extension, schedule.emissions_per_second_x64)?
+        .checked_add(emitted_amount(time_left, emissions_increase)?)
+        .ok_or_else(|| format_err!("Reward amount overflow"))
+}
+
+/// The token account a reward authority funds a stream from, or collects
+/// leftovers into: its associated token account for the reward mint.
+#[derive(Debug, Clone, Copy)]
+pub struct RewardFunder {
+    pub token_account: Pubkey,
+    pub token_program: Pubkey,
+    pub balance: u64,
+    pub transfer_fee: Option<TransferFee>,
+}
+
+impl RewardFunder {
+    /// Reads the mint and `funder`'s token account for it, checking the
+    /// account belongs to `funder`, holds `reward_mint` and is not frozen.
+    pub fn load(rpc_client: &RpcClient, funder: &Pubkey, reward_mint: &Pubkey) -> Result<Self> {
+        let epoch = rpc_client.get_epoch_info()?.epoch;
+        let mint_account = rpc_client
+            .get_account(reward_mint)
+            .map_err(|_| format_err!("Reward mint {} not found", reward_mint))?;
+        let token_program = mint_account.owner;
+        if token_program != spl_token::id() && token_program != spl_token_2022::id() {
+            return Err(format_err!(
+                "Reward mint {} is owned by {}, which is not a token program",
+                reward_mint,
+                token_program
+            ));
+        }
+        let transfer_fee = epoch_transfer_fee(&mint_account.data, &token_program, epoch)?;
+
+        let token_account =
+            get_associated_token_address_with_program_id(funder, reward_mint, &token_program);
+        let account = rpc_client.get_account(&token_account).map_err(|_| {
+            format_err!(
+                "{} has no token account {} for reward mint {}",
+                funder,
+                token_account,
+                reward_mint
+            )
+        })?;
+        if account.owner != token_program {
+            return Err(format_err!(
+                "Token account {} is not owned by {}",
+                token_account,
+                token_program
+            ));
+        }
+        let state = StateWithExtensions::<TokenAccount>::unpack(&account.data)?.base;
+        if state.mint != *reward_mint || state.owner != *funder {
+            return Err(format_err!(
+                "Token account {} holds {} for {}, expected {} for {}",
+                token_account,
+                state.mint,
+                state.owner,
+                reward_mint,
+                funder
+            ));
+        }
+        if state.state == AccountState::Frozen {
+            return Err(format_err!("Token account {} is frozen", token_account));
+        }
+
+        Ok(Self {
+            token_account,
+            token_program,
+            balance: state.amount,
+            transfer_fee,
+        })
+    }
+
+    /// Checks the account can send `amount` into a reward vault, returning
+    /// what leaves the account once the mint's transfer fee is added.
+    pub fn ensure_covers(&self, amount: u64) -> Result<u64> {
+        let required = amount
+            .checked_add(transfer_inverse_fee(self.transfer_fee.as_ref(), amount)?)
+            .ok_or_else(|| format_err!("Reward amount overflow"))?;
+        if self.balance < required {
+            return Err(format_err!(
+                "Token account {} holds {}, but funding the reward needs {}",
+                self.token_account,
+                self.balance,
+                required
+            ));
+        }
+        Ok(required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn schedule(open_time: u64, end_time: u64, emissions_per_second_x64: u128) -> RewardSchedule {
+        RewardSchedule {
+            open_time,
+            end_time,
+            emissions_per_second_x64,
+        }
+    }
+
+    fn running_reward(end_time: u64, emissions_per_second_x64: u128) -> RewardInfo {
+        RewardInfo {
+            reward_state: 2,
+            open_time: end_time - MIN_REWARD_PERIOD,
+            end_time,
+            last_update_time: NOW,
+            emissions_per_second_x64,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_requires_a_future_open_time() {
+        let open_time = NOW + 1;
+        assert!(
+            schedule(open_time, open_time + MIN_REWARD_PERIOD, Q64)
+                .validate(NOW)
+                .is_ok()
+        );
+        assert!(
+            schedule(NOW, NOW + MIN_REWARD_PERIOD, Q64)
+                .validate(NOW)
+                .is_err()
+        );
+        assert!(
+            schedule(NOW - 1, NOW + MIN_REWARD_PERIOD, Q64)
+                .validate(NOW)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn validate_checks_period_and_emissions() {
+        let open_time = NOW + 60;
+        let valid = |end_time, emissions| schedule(open_time, end_time, emissions).validate(NOW);
+        assert!(valid(open_time + MIN_REWARD_PERIOD, Q64).is_ok());
+        assert!(valid(open_time + MAX_REWARD_PERIOD, Q64).is_ok());
+        assert!(valid(open_time + MIN_REWARD_PERIOD - 1, Q64).is_err());
+        assert!(valid(open_time + MAX_REWARD_PERIOD + 1, Q64).is_err());
+        assert!(valid(open_time, Q64).is_err());
+        assert!(valid(open_time - 1, Q64).is_err());
+        assert!(valid(open_time + MIN_REWARD_PERIOD, 0).is_err());
+    }
+
+    #[test]
+    fn total_amount_spreads_evenly() {
+        let schedule =
+            RewardSchedule::from_total_amount(NOW, NOW + MIN_REWARD_PERIOD, MIN_REWARD_PERIOD)
+                .unwrap();
+        assert_eq!(schedule.emissions_per_second_x64, Q64);
+        assert_eq!(schedule.total_amount().unwrap(), MIN_REWARD_PERIOD);
+    }
+
+    #[test]
+    fn total_amount_rounds_the_rate_down_and_the_amount_back_up() {
+        let schedule =
+            RewardSchedule::from_total_amount(NOW, NOW + MIN_REWARD_PERIOD, 1_000_000).unwrap();
+        assert_eq!(
+            schedule.emissions_per_second_x64,
+            1_000_000 * Q64 / u128::from(MIN_REWARD_PERIOD)
+        );
+        // The vault is never asked for more than the amount spread.
+        assert_eq!(schedule.total_amount().unwrap(), 1_000_000);
+
+        // A rate just over one token a second needs one extra token.
+        assert_eq!(
+            RewardSchedule {
+                open_time: NOW,
+                end_time: NOW + 1000,
+                emissions_per_second_x64: Q64 + 1,
+            }
+            .total_amount()
+            .unwrap(),
+            1001
+        );
+    }
+
+    #[test]
+    fn total_amount_rejects_an_empty_period() {
+        assert!(RewardSchedule::from_total_amount(NOW, NOW, 1_000).is_err());
+        assert!(RewardSchedule::from_total_amount(NOW, NOW - 1, 1_000).is_err());
+    }
+
+    #[test]
+    fn ended_reward_restarts_with_a_fresh_schedule() {
+        let ended = running_reward(NOW - 10, Q64);
+        let open_time = NOW + 60;
+        let restart = schedule(open_time, open_time + MIN_REWARD_PERIOD, 2 * Q64);
+        assert_eq!(
+            reward_update_amount(&ended, &restart, NOW).unwrap(),
+            2 * MIN_REWARD_PERIOD
+        );
+
+        // Lower emissions are fine on a restart.
+        let lower = schedule(open_time, open_time + MIN_REWARD_PERIOD, Q64 / 2);
+        assert_eq!(
+            reward_update_amount(&ended, &lower, NOW).unwrap(),
+            MIN_REWARD_PERIOD / 2
+        );
+
+        // A restart is validated like a new reward.
+        let started = schedule(NOW, NOW + MIN_REWARD_PERIOD, Q64);
+        assert!(reward_update_amount(&ended, &started, NOW).is_err());
+        // Ending exactly now counts as ended.
+        let ending_now = running_reward(NOW, Q64);
+        assert!(reward_update_amount(&ending_now, &restart, NOW).is_ok());
+    }
+
+    #[test]
+    fn running_reward_changes_only_in_its_extension_window() {
+        let end_time = NOW + REWARD_EXTENSION_WINDOW;
+        let extend = schedule(end_time, end_time + MIN_REWARD_PERIOD, Q64);
+        assert_eq!(
+            reward_update_amount(&running_reward(end_time, Q64), &extend, NOW).unwrap(),
+            MIN_REWARD_PERIOD
+        );
+
+        let end_time = NOW + REWARD_EXTENSION_WINDOW + 1;
+        let extend = schedule(end_time, end_time + MIN_REWARD_PERIOD, Q64);
+        assert!(reward_update_amount(&running_reward(end_time, Q64), &extend, NOW).is_err());
+    }
+
+    #[test]
+    fn running_reward_pays_for_extension_and_increase() {
+        let end_time = NOW + 3600;
+        let reward = running_reward(end_time, Q64);
+        let raised = schedule(end_time, end_time + MIN_REWARD_PERIOD, 2 * Q64);
+        // The whole extension at the new rate, plus the increase for the
+        // time the current period still runs.
+        assert_eq!(
+            reward_update_amount(&reward, &raised, NOW).unwrap(),
+            2 * MIN_REWARD_PERIOD + 3600
+        );
+    }
+
+    #[test]
+    fn running_reward_rejects_bad_extensions() {
+        let end_time = NOW + 3600;
+        let reward = running_reward(end_time, Q64);
+        let update = |end, emissions| {
+            reward_update_amount(&reward, &schedule(end_time, end, emissions), NOW)
+        };
+        assert!(update(end_time - 1, Q64).is_err());
+        assert!(update(end_time + MIN_REWARD_PERIOD - 1, Q64).is_err());
+        assert!(update(end_time + MAX_REWARD_PERIOD + 1, Q64).is_err());
+        assert!(update(end_time + MAX_REWARD_PERIOD, Q64).is_ok());
+        // Emissions cannot be lowered while the reward runs.
+        assert!(update(end_time + MIN_REWARD_PERIOD, Q64 - 1).is_err());
+    }
+
+    #[test]
+    fn running_reward_amount_rounds_up() {
+        let end_time = NOW + 1000;
+        let reward = running_reward(end_time, Q64);
+        let raised = schedule(end_time, end_time + MIN_REWARD_PERIOD, Q64 + 1);
+        // Each part of the amount rounds up on its own.
+        assert_eq!(
+            reward_update_amount(&reward, &raised, NOW).unwrap(),
+            MIN_REWARD_PERIOD + 1 + 1
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use super::clmm_pool::ClmmPool;
+use super::clmm_reward::{RewardFunder, RewardSchedule, fetch_cluster_time, reward_update_amount};
+
+/// Restarts an ended reward stream, or extends a running one, as its
+/// authority. Missing values keep the stream's current open time and
+/// emissions, and any extra funding comes from the user's token account.
+pub fn clmm_set_reward_params(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    reward_mint: &Pubkey,
+    open_time: Option<u64>,
+    end_time: u64,
+    emissions_per_second_x64: Option<u128>,
+) -> Result<Signature> {
+    pool.ensure_owner(&user.pubkey())?;
+
+    println!("Fetching live pool data...");
+    pool.refresh(rpc_client)?;
+    let now = fetch_cluster_time(rpc_client)?;
+
+    let reward_index = pool.reward_index(reward_mint)?;
+    let reward_info = pool.state.reward_infos[usize::from(reward_index)];
+    if reward_info.authority != user.pubkey() {
+        return Err(format_err!(
+            "Reward {} is managed by {}, not {}",
+            reward_mint,
+            reward_info.authority,
+            user.pubkey()
+        ));
+    }
+
+    let schedule = RewardSchedule {
+        open_time: open_time.unwrap_or(reward_info.open_time),
+        end_time,
+        emissions_per_second_x64: emissions_per_second_x64
+            .unwrap_or(reward_info.emissions_per_second_x64),
+    };
+    let amount = reward_update_amount(&reward_info, &schedule, now)?;
+
+    let funder = RewardFunder::load(rpc_client, &user.pubkey(), reward_mint)?;
+    let required = funder.ensure_covers(amount)?;
+
+    println!(
+        "Reward {}: {} to {} at {} per second (Q64.64), was {} to {} at {}",
+        reward_index,
+        schedule.open_time,
+        schedule.end_time,
+        schedule.emissions_per_second_x64,
+        reward_info.open_time,
+        reward_info.end_time,
+        reward_info.emissions_per_second_x64
+    );
+    println!(
+        "Additional Funding: {} (with transfer fee {}) from {}, balance {}",
+        amount, required, funder.token_account, funder.balance
+    );
+
+    let reward = pool.reward_accounts_for(reward_mint, &funder.token_program);
+    let instruction = pool.set_reward_params_instruction(reward_index, &reward, &schedule)?;
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending set_reward_params transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok(signature)
+}
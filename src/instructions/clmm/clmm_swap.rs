@@ -0,0 +1,135 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use super::clmm_config::fetch_amm_config;
+use super::clmm_pool::ClmmPool;
+use super::clmm_quote::{
+    SwapAmount, default_sqrt_price_limit, quote_swap, validate_sqrt_price_limit,
+};
+use super::clmm_tick_array::{
+    fetch_tick_array_bitmap_extension, fetch_tick_arrays, swap_tick_array_start_indexes,
+    tick_array_address,
+};
+use crate::utils::slippage::Slippage;
+
+/// Initialised tick arrays passed to a swap. More let a larger swap cross
+/// more ranges, at the cost of transaction size.
+pub const SWAP_TICK_ARRAY_COUNT: usize = 3;
+
+/// Swaps `input_mint` for the pool's other token. The price may move no
+/// further than `sqrt_price_limit_x64`, which defaults to the end of the
+/// price range; the other side is bounded by `slippage` around a local
+/// quote.
+pub fn clmm_swap(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut ClmmPool,
+    input_mint: &Pubkey,
+    amount: SwapAmount,
+    sqrt_price_limit_x64: Option<u128>,
+    slippage: Slippage,
+) -> Result<Signature> {
+    pool.ensure_owner(&user.pubkey())?;
+    slippage.validate()?;
+    let zero_for_one = pool.is_token_0(input_mint)?;
+
+    println!(
+        "Swapping {:?} of {} in pool {}",
+        amount, input_mint, pool.pool_id
+    );
+
+    println!("Fetching live pool data...");
+    let transfer_fees = pool.refresh(rpc_client)?;
+    let amm_config = fetch_amm_config(rpc_client, &pool.state.amm_config)?;
+    let bitmap_extension =
+        fetch_tick_array_bitmap_extension(rpc_client, &pool.program_id, &pool.pool_id)?;
+
+    let sqrt_price_limit_x64 =
+        sqrt_price_limit_x64.unwrap_or_else(|| default_sqrt_price_limit(zero_for_one));
+    validate_sqrt_price_limit(
+        pool.state.sqrt_price_x64,
+        sqrt_price_limit_x64,
+        zero_for_one,
+    )?;
+
+    let start_indexes = swap_tick_array_start_indexes(
+        &pool.state,
+        &bitmap_extension,
+        zero_for_one,
+        SWAP_TICK_ARRAY_COUNT,
+    );
+    if start_indexes.is_empty() {
+        return Err(format_err!(
+            "Pool {} has no initialised tick arrays in the swap direction",
+            pool.pool_id
+        ));
+    }
+    println!("Tick Arrays: {:?}", start_indexes);
+    let tick_arrays: Vec<Pubkey> = start_indexes
+        .iter()
+        .map(|start_index| tick_array_address(&pool.program_id, &pool.pool_id, *start_index))
+        .collect();
+
+    let tick_array_states =
+        fetch_tick_arrays(rpc_client, &pool.program_id, &pool.pool_id, &start_indexes)?;
+
+    let quote = quote_swap(
+        &pool.state,
+        &amm_config,
+        &tick_array_states,
+        &transfer_fees,
+        zero_for_one,
+        amount,
+        sqrt_price_limit_x64,
+    )?;
+    let other_amount_threshold = match amount {
+        SwapAmount::ExactIn(_) => slippage.min_amount(quote.amount_out)?,
+        SwapAmount::ExactOut(_) => slippage.max_amount(quote.amount_in)?,
+    };
+
+    println!(
+        "Trade Fee: {}, Transfer Fees: {} in, {} out",
+        quote.trade_fee, quote.transfer_fee_in, quote.transfer_fee_out
+    );
+    match amount {
+        SwapAmount::ExactIn(_) => println!(
+            "Expected Out: {}, Min Accepted: {}",
+            quote.amount_out, other_amount_threshold
+        ),
+        SwapAmount::ExactOut(_) => println!(
+            "Expected In: {}, Max Allowed: {}",
+            quote.amount_in, other_amount_threshold
+        ),
+    }
+    println!(
+        "Tick: {} -> {}, crossing tick arrays {:?}",
+        pool.state.tick_current, quote.tick_after, quote.tick_arrays_touched
+    );
+
+    let instruction = pool.swap_instruction(
+        zero_for_one,
+        amount.amount(),
+        other_amount_threshold,
+        sqrt_price_limit_x64,
+        amount.is_base_input(),
+        &tick_arrays,
+    )?;
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending swap transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok(signature)
+}
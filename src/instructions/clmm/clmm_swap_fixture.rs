@@ -0,0 +1,199 @@
+use anyhow::{Result, format_err};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use std::fs;
+use std::path::Path;
+
+use super::clmm_config::AmmConfig;
+use super::clmm_pool::{ClmmPool, PoolState};
+use super::clmm_tick_array::{
+    TickArrayState, fetch_tick_array_bitmap_extension, swap_tick_array_start_indexes,
+    tick_array_address,
+};
+use super::swap_simulator::{SwapSimulation, simulate_swap};
+
+/// Tick arrays captured with a fixture, enough for most swaps to complete.
+const FIXTURE_TICK_ARRAY_COUNT: usize = 5;
+
+/// Results recorded for a swap, typically from the program's `SwapEvent`.
+/// Only the fields present are checked.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ExpectedSwap {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_in: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_out: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_amount: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqrt_price_x64: Option<u128>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tick_current: Option<i32>,
+}
+
+/// A swap and the accounts it runs against, with account data stored as
+/// base64 exactly as fetched, so simulations can be replayed offline.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SwapFixture {
+    pub pool_state: String,
+    pub amm_config: String,
+    pub tick_arrays: Vec<String>,
+    pub zero_for_one: bool,
+    /// Input when `is_base_input`, output otherwise, net of transfer fees.
+    pub amount_specified: u64,
+    pub is_base_input: bool,
+    pub sqrt_price_limit_x64: u128,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<ExpectedSwap>,
+}
+
+impl SwapFixture {
+    /// Captures the live state a swap on `pool` would run against.
+    pub fn capture(
+        rpc_client: &RpcClient,
+        pool: &ClmmPool,
+        zero_for_one: bool,
+        amount_specified: u64,
+        is_base_input: bool,
+        sqrt_price_limit_x64: u128,
+    ) -> Result<Self> {
+        let bitmap_extension =
+            fetch_tick_array_bitmap_extension(rpc_client, &pool.program_id, &pool.pool_id)?;
+        let tick_array_addresses: Vec<_> = swap_tick_array_start_indexes(
+            &pool.state,
+            &bitmap_extension,
+            zero_for_one,
+            FIXTURE_TICK_ARRAY_COUNT,
+        )
+        .into_iter()
+        .map(|start_index| tick_array_address(&pool.program_id, &pool.pool_id, start_index))
+        .collect();
+
+        // Pool, config and tick arrays in one round trip so they agree.
+        let mut addresses = vec![pool.pool_id, pool.state.amm_config];
+        addresses.extend(&tick_array_addresses);
+        let accounts = rpc_client
+            .get_multiple_accounts(&addresses)?
+            .into_iter()
+            .zip(&addresses)
+            .map(|(account, address)| {
+                account
+                    .map(|account| BASE64.encode(account.data))
+                    .ok_or_else(|| format_err!("Account {} not found", address))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            pool_state: accounts[0].clone(),
+            amm_config: accounts[1].clone(),
+            tick_arrays: accounts[2..].to_vec(),
+            zero_for_one,
+            amount_specified,
+            is_base_input,
+            sqrt_price_limit_x64,
+            expected: None,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn simulate(&self) -> Result<SwapSimulation> {
+        let pool_state = PoolState::from_account_data(&BASE64.decode(&self.pool_state)?)?;
+        let amm_config = AmmConfig::from_account_data(&BASE64.decode(&self.amm_config)?)?;
+        let tick_arrays = self
+            .tick_arrays
+            .iter()
+            .map(|data| TickArrayState::from_account_data(&BASE64.decode(data)?))
+            .collect::<Result<Vec<_>>>()?;
+        simulate_swap(
+            &pool_state,
+            &amm_config,
+            &tick_arrays,
+            self.zero_for_one,
+            self.amount_specified,
+            self.sqrt_price_limit_x64,
+            self.is_base_input,
+        )
+    }
+
+    /// Describes every expected value `simulation` disagrees with.
+    pub fn mismatches(&self, simulation: &SwapSimulation) -> Vec<String> {
+        let Some(expected) = &self.expected else {
+            return Vec::new();
+        };
+        let mut mismatches = Vec::new();
+        let mut check = |name: &str, expected: Option<String>, actual: String| {
+            if let Some(expected) = expected
+                && expected != actual
+            {
+                mismatches.push(format!(
+                    "{}: expected {}, simulated {}",
+                    name, expected, actual
+                ));
+            }
+        };
+        check(
+            "amount_in",
+            expected.amount_in.map(|value| value.to_string()),
+            simulation.amount_in.to_string(),
+        );
+        check(
+            "amount_out",
+            expected.amount_out.map(|value| value.to_string()),
+            simulation.amount_out.to_string(),
+        );
+        check(
+            "fee_amount",
+            expected.fee_amount.map(|value| value.to_string()),
+            simulation.fee_amount.to_string(),
+        );
+        check(
+            "sqrt_price_x64",
+            expected.sqrt_price_x64.map(|value| value.to_string()),
+            simulation.sqrt_price_x64.to_string(),
+        );
+        check(
+            "tick_current",
+            expected.tick_current.map(|value| value.to_string()),
+            simulation.tick_current.to_string(),
+        );
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_replay_without_mismatches() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/clmm_swap");
+        let mut replayed = 0;
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let fixture = SwapFixture::load(&path).unwrap();
+            assert!(fixture.expected.is_some(), "{}", path.display());
+            let simulation = fixture.simulate().unwrap();
+            let mismatches = fixture.mismatches(&simulation);
+            assert!(
+                mismatches.is_empty(),
+                "{}: {:?}",
+                path.display(),
+                mismatches
+            );
+            replayed += 1;
+        }
+        assert!(replayed > 0, "no fixtures in {}", dir.display());
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::{Result, format_err};
+use borsh::BorshDeserialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use super::clmm_pool::{PoolState, REWARD_NUM};
+use super::tick_math::{MAX_TICK, MIN_TICK};
+
+pub const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+pub const POOL_TICK_ARRAY_BITMAP_SEED: &[u8] = b"pool_tick_array_bitmap_extension";
+
+const TICK_ARRAY_STATE_DISCRIMINATOR: [u8; 8] = [192, 155, 85, 205, 49, 249, 129, 42];
+const TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR: [u8; 8] = [60, 150, 36, 219, 97, 128, 139, 153];
+
+/// Number of 512-bit bitmaps the extension keeps on each side of zero.
+pub const EXTENSION_TICKARRAY_BITMAP_SIZE: usize = 14;
+
+pub const TICK_ARRAY_SIZE: i32 = 60;
+/// Tick arrays tracked either side of zero by the pool's own bitmap.
+pub const TICK_ARRAY_BITMAP_SIZE: i32 = 512;
+
+/// Number of ticks a single tick array spans.
+pub fn tick_count(tick_spacing: u16) -> i32 {
+    TICK_ARRAY_SIZE * tick_spacing as i32
+}
+
+/// Start index of the tick array holding `tick_index`.
+pub fn get_array_start_index(tick_index: i32, tick_spacing: u16) -> i32 {
+    let ticks_in_array = tick_count(tick_spacing);
+    let mut start = tick_index / ticks_in_array;
+    if tick_index < 0 && tick_index % ticks_in_array != 0 {
+        start -= 1;
+    }
+    start * ticks_in_array
+}
+
+pub fn tick_array_address(program_id: &Pubkey, pool_id: &Pubkey, start_index: i32) -> Pubkey {
+    let (tick_array, _) = Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED,
+            pool_id.as_ref(),
+            &start_index.to_be_bytes(),
+        ],
+        program_id,
+    );
+    tick_array
+}
+
+pub fn tick_array_bitmap_extension_address(program_id: &Pubkey, pool_id: &Pubkey) -> Pubkey {
+    let (bitmap_extension, _) =
+        Pubkey::find_program_address(&[POOL_TICK_ARRAY_BITMAP_SEED, pool_id.as_ref()], program_id);
+    bitmap_extension
+}
+
+/// Range of tick array start indexes `[min, max)` covered by the pool's
+/// default bitmap.
+pub fn default_bitmap_start_index_range(tick_spacing: u16) -> (i32, i32) {
+    let mut max_tick_boundary = tick_count(tick_spacing) * TICK_ARRAY_BITMAP_SIZE;
+    let mut min_tick_boundary = -max_tick_boundary;
+    if max_tick_boundary > MAX_TICK {
+        max_tick_boundary =
+            get_array_start_index(MAX_TICK, tick_spacing) + tick_count(tick_spacing);
+    }
+    if min_tick_boundary < MIN_TICK {
+        min_tick_boundary = get_array_start_index(MIN_TICK, tick_spacing);
+    }
+    (min_tick_boundary, max_tick_boundary)
+}
+
+/// Whether the tick array holding `tick_index` is tracked by the bitmap
+/// extension account rather than the pool itself, in which case the
+/// extension must be passed to instructions that touch it.
+pub fn is_overflow_default_tick_array_bitmap(tick_index: i32, tick_spacing: u16) -> bool {
+    let (min_start_index, max_start_index) = default_bitmap_start_index_range(tick_spacing);
+    let start_index = get_array_start_index(tick_index, tick_spacing);
+    start_index >= max_start_index || start_index < min_start_index
+}
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone, Copy, Default)]
+pub struct TickState {
+    pub tick: i32,
+    /// Liquidity added when the price crosses this tick upwards.
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_0_x64: u128,
+    pub fee_growth_outside_1_x64: u128,
+    pub reward_growths_outside_x64: [u128; REWARD_NUM],
+    pub padding: [u32; 13],
+}
+
+impl TickState {
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross != 0
+    }
+}
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct TickArrayState {
+    pub pool_id: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: [TickState; TICK_ARRAY_SIZE as usize],
+    pub initialized_tick_count: u8,
+    pub recent_epoch: u64,
+    pub padding: [u8; 107],
+}
+
+impl TickArrayState {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != TICK_ARRAY_STATE_DISCRIMINATOR {
+            return Err(format_err!("Account is not a CLMM TickArrayState"));
+        }
+        Ok(TickArrayState::deserialize(&mut &data[8..])?)
+    }
+
+    /// The next initialised tick at or below (`zero_for_one`) or strictly
+    /// above `tick_index`, if this array holds `tick_index` and has one.
+    pub fn next_initialized_tick(
+        &self,
+        tick_index: i32,
+        tick_spacing: u16,
+        zero_for_one: bool,
+    ) -> Option<&TickState> {
+        if get_array_start_index(tick_index, tick_spacing) != self.start_tick_index {
+            return None;
+        }
+        let offset = ((tick_index - self.start_tick_index) / tick_spacing as i32) as usize;
+        if zero_for_one {
+            self.ticks[..=offset]
+                .iter()
+                .rev()
+                .find(|tick| tick.is_initialized())
+        } else {
+            self.ticks[offset + 1..]
+                .iter()
+                .find(|tick| tick.is_initialized())
+        }
+    }
+
+    /// The first initialised tick met when entering this array in the swap
+    /// direction.
+    pub fn first_initialized_tick(&self, zero_for_one: bool) -> Option<&TickState> {
+        if zero_for_one {
+            self.ticks.iter().rev().find(|tick| tick.is_initialized())
+        } else {
+            self.ticks.iter().find(|tick| tick.is_initialized())
+        }
+    }
+
+    /// The state of `tick_index`, which must be a usable tick in this array.
+    pub fn tick(&self, tick_index: i32, tick_spacing: u16) -> Result<&TickState> {
+        let offset = self.tick_offset(tick_index, tick_spacing)?;
+        Ok(&self.ticks[offset])
+    }
+
+    pub fn tick_mut(&mut self, tick_index: i32, tick_spacing: u16) -> Result<&mut TickState> {
+        let offset = self.tick_offset(tick_index, tick_spacing)?;
+        Ok(&mut self.ticks[offset])
+    }
+
+    fn tick_offset(&self, tick_index: i32, tick_spacing: u16) -> Result<usize> {
+        let offset = tick_index - self.start_tick_index;
+        if get_array_start_index(tick_index, tick_spacing) != self.start_tick_index
+            || offset % tick_spacing as i32 != 0
+        {
+            return Err(format_err!(
+                "Tick {} is not a usable tick of the array starting at {}",
+                tick_index,
+                self.start_tick_index
+            ));
+        }
+        Ok((offset / tick_spacing as i32) as usize)
+    }
+}
+
+/// Reads the tick arrays at `start_indexes`, in order, failing if any is
+/// missing.
+pub fn fetch_tick_arrays(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+    start_indexes: &[i32],
+) -> Result<Vec<TickArrayState>> {
+    let addresses: Vec<Pubkey> = start_indexes
+        .iter()
+        .map(|start_index| tick_array_address(program_id, pool_id, *start_index))
+        .collect();
+    rpc_client
+        .get_multiple_accounts(&addresses)?
+        .into_iter()
+        .zip(start_indexes)
+        .map(|(account, start_index)| {
+            let account = account
+                .ok_or_else(|| format_err!("Tick array starting at {} not found", start_index))?;
+            TickArrayState::from_account_data(&account.data)
+        })
+        .collect()
+}
+
+/// Tracks which tick arrays beyond the pool's default bitmap are initialised.
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct TickArrayBitmapExtension {
+    pub pool_id: Pubkey,
+    pub positive_tick_array_bitmap: [[u64; 8]; EXTENSION_TICKARRAY_BITMAP_SIZE],
+    pub negative_tick_array_bitmap: [[u64; 8]; EXTENSION_TICKARRAY_BITMAP_SIZE],
+}
+
+impl TickArrayBitmapExtension {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != TICK_ARRAY_BITMAP_EXTENSION_DISCRIMINATOR {
+            return Err(format_err!(
+                "Account is not a CLMM TickArrayBitmapExtension"
+            ));
+        }
+        Ok(TickArrayBitmapExtension::deserialize(&mut &data[8..])?)
+    }
+
+    /// Whether the tick array at `start_index`, which must lie outside the
+    /// default bitmap, is initialised.
+    fn is_initialized(&self, start_index: i32, tick_spacing: u16) -> bool {
+        let ticks_in_one_bitmap = tick_count(tick_spacing) * TICK_ARRAY_BITMAP_SIZE;
+        let mut offset = start_index.abs() / ticks_in_one_bitmap - 1;
+        if start_index < 0 && start_index.abs() % ticks_in_one_bitmap == 0 {
+            offset -= 1;
+        }
+        let bitmap = if start_index < 0 {
+            self.negative_tick_array_bitmap.get(offset as usize)
+        } else {
+            self.positive_tick_array_bitmap.get(offset as usize)
+        };
+        let Some(bitmap) = bitmap else {
+            return false;
+        };
+
+        let remainder = start_index.abs() % ticks_in_one_bitmap;
+        let mut bit = remainder / tick_count(tick_spacing);
+        if start_index < 0 && remainder != 0 {
+            bit = TICK_ARRAY_BITMAP_SIZE - bit;
+        }
+        bitmap[bit as usize / 64] & (1 << (bit % 64)) != 0
+    }
+}
+
+/// Reads the pool's bitmap extension, which every pool gets on creation.
+pub fn fetch_tick_array_bitmap_extension(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_id: &Pubkey,
+) -> Result<TickArrayBitmapExtension> {
+    let address = tick_array_bitmap_extension_address(program_id, pool_id);
+    let account = rpc_client.get_account(&address)?;
+    TickArrayBitmapExtension::from_account_data(&account.data)
+}
+
+/// Whether the tick array at `start_index` holds any initialised tick,
+/// according to the pool's bitmaps.
+pub fn is_tick_array_initialized(
+    pool_state: &PoolState,
+    bitmap_extension: &TickArrayBitmapExtension,
+    start_index: i32,
+) -> bool {
+    let tick_spacing = pool_state.tick_spacing;
+    if is_overflow_default_tick_array_bitmap(start_index, tick_spacing) {
+        return bitmap_extension.is_initialized(start_index, tick_spacing);
+    }
+    let bit = start_index / tick_count(tick_spacing) + TICK_ARRAY_BITMAP_SIZE;
+    pool_state.tick_array_bitmap[bit as usize / 64] & (1 << (bit % 64)) != 0
+}
+
+/// Start indexes of up to `max_count` initialised tick arrays a swap would
+/// walk through, beginning with the one holding the current tick.
+pub fn swap_tick_array_start_indexes(
+    pool_state: &PoolState,
+    bitmap_extension: &TickArrayBitmapExtension,
+    zero_for_one: bool,
+    max_count: usize,
+) -> Vec<i32> {
+    let tick_spacing = pool_state.tick_spacing;
+    let step = tick_count(tick_spacing);
+    let first_start_index = get_array_start_index(MIN_TICK, tick_spacing);
+    let last_start_index = get_array_start_index(MAX_TICK, tick_spacing);
+
+    let mut start_indexes = Vec::with_capacity(max_count);
+    let mut start_index = get_array_start_index(pool_state.tick_current, tick_spacing);
+    while start_indexes.len() < max_count
+        && (first_start_index..=last_start_index).contains(&start_index)
+    {
+        if is_tick_array_initialized(pool_state, bitmap_extension, start_index) {
+            start_indexes.push(start_index);
+        }
+        start_index += if zero_for_one { -step } else { step };
+    }
+    start_indexes
+}
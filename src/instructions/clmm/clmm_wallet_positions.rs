@@ -0,0 +1,87 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+use super::clmm_pool::PoolState;
+use super::clmm_position::{MAX_MULTIPLE_ACCOUNTS, PersonalPositionState, fetch_owned_positions};
+use super::liquidity_math::get_delta_amounts_signed;
+use super::tick_math::TickRange;
+
+/// A position as it stands against its pool's current price.
+#[derive(Debug, Clone, Copy)]
+pub struct PositionSummary {
+    pub nft_mint: Pubkey,
+    pub pool_id: Pubkey,
+    pub tick_range: TickRange,
+    pub liquidity: u128,
+    pub tick_current: i32,
+    pub in_range: bool,
+    /// Tokens the position's liquidity would withdraw now, before transfer
+    /// fees and excluding uncollected fees.
+    pub amount_0: u64,
+    pub amount_1: u64,
+}
+
+impl PositionSummary {
+    pub fn new(
+        nft_mint: &Pubkey,
+        position: &PersonalPositionState,
+        pool_state: &PoolState,
+    ) -> Result<Self> {
+        let tick_range = position.tick_range();
+        let (amount_0, amount_1) = get_delta_amounts_signed(
+            pool_state.tick_current,
+            pool_state.sqrt_price_x64,
+            tick_range.tick_lower,
+            tick_range.tick_upper,
+            -i128::try_from(position.liquidity)?,
+        )?;
+        Ok(Self {
+            nft_mint: *nft_mint,
+            pool_id: position.pool_id,
+            tick_range,
+            liquidity: position.liquidity,
+            tick_current: pool_state.tick_current,
+            in_range: (tick_range.tick_lower..tick_range.tick_upper)
+                .contains(&pool_state.tick_current),
+            amount_0,
+            amount_1,
+        })
+    }
+}
+
+/// Every CLMM position `owner` holds, summarised against the current state
+/// of its pool. Pools are fetched in batches, once each.
+pub fn fetch_position_summaries(
+    rpc_client: &RpcClient,
+    owner: &Pubkey,
+) -> Result<Vec<PositionSummary>> {
+    let positions = fetch_owned_positions(rpc_client, owner)?;
+
+    let mut pool_ids: Vec<_> = positions
+        .iter()
+        .map(|position| position.state.pool_id)
+        .collect();
+    pool_ids.sort();
+    pool_ids.dedup();
+
+    let mut pool_states = HashMap::new();
+    for chunk in pool_ids.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        for (pool_id, account) in chunk.iter().zip(rpc_client.get_multiple_accounts(chunk)?) {
+            let account = account.ok_or_else(|| format_err!("Pool {} not found", pool_id))?;
+            pool_states.insert(*pool_id, PoolState::from_account_data(&account.data)?);
+        }
+    }
+
+    positions
+        .iter()
+        .map(|position| {
+            PositionSummary::new(
+                &position.nft_mint,
+                &position.state,
+                &pool_states[&position.state.pool_id],
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,296 @@
+use anyhow::{Result, format_err};
+use std::fmt;
+
+use super::big_num::U256;
+use super::tick_math::get_sqrt_price_at_tick;
+
+const Q64: u128 = 1 << 64;
+
+// Rounding follows the program: liquidity is always rounded down, token
+// amounts up when paid into a position and down when paid out of it.
+
+/// A token amount that does not fit in a `u64`. Kept distinct so swap math
+/// can tell an unreachable price target apart from other failures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAmountOverflow;
+
+impl fmt::Display for TokenAmountOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token amount overflows u64")
+    }
+}
+
+impl std::error::Error for TokenAmountOverflow {}
+
+fn ordered(sqrt_price_a_x64: u128, sqrt_price_b_x64: u128) -> (u128, u128) {
+    if sqrt_price_a_x64 > sqrt_price_b_x64 {
+        (sqrt_price_b_x64, sqrt_price_a_x64)
+    } else {
+        (sqrt_price_a_x64, sqrt_price_b_x64)
+    }
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    if value > U256::from(u128::MAX) {
+        return Err(format_err!("Liquidity overflows u128"));
+    }
+    Ok(value.as_u128())
+}
+
+fn to_u64(value: U256) -> Result<u64> {
+    if value > U256::from(u64::MAX) {
+        return Err(TokenAmountOverflow.into());
+    }
+    Ok(value.as_u64())
+}
+
+/// Liquidity that `amount_0` of token 0 provides between two sqrt prices.
+pub fn get_liquidity_from_amount_0(
+    sqrt_price_a_x64: u128,
+    sqrt_price_b_x64: u128,
+    amount_0: u64,
+) -> Result<u128> {
+    let (sqrt_price_a_x64, sqrt_price_b_x64) = ordered(sqrt_price_a_x64, sqrt_price_b_x64);
+    if sqrt_price_a_x64 == sqrt_price_b_x64 {
+        return Err(format_err!("Price range is empty"));
+    }
+    let intermediate = U256::from(sqrt_price_a_x64)
+        .mul_div_floor(U256::from(sqrt_price_b_x64), U256::from(Q64))
+        .ok_or_else(|| format_err!("Liquidity overflows u128"))?;
+    to_u128(
+        U256::from(amount_0)
+            .mul_div_floor(
+                intermediate,
+                U256::from(sqrt_price_b_x64 - sqrt_price_a_x64),
+            )
+            .ok_or_else(|| format_err!("Liquidity overflows u128"))?,
+    )
+}
+
+/// Liquidity that `amount_1` of token 1 provides between two sqrt prices.
+pub fn get_liquidity_from_amount_1(
+    sqrt_price_a_x64: u128,
+    sqrt_price_b_x64: u128,
+    amount_1: u64,
+) -> Result<u128> {
+    let (sqrt_price_a_x64, sqrt_price_b_x64) = ordered(sqrt_price_a_x64, sqrt_price_b_x64);
+    if sqrt_price_a_x64 == sqrt_price_b_x64 {
+        return Err(format_err!("Price range is empty"));
+    }
+    to_u128(
+        U256::from(amount_1)
+            .mul_div_floor(
+                U256::from(Q64),
+                U256::from(sqrt_price_b_x64 - sqrt_price_a_x64),
+            )
+            .ok_or_else(|| format_err!("Liquidity overflows u128"))?,
+    )
+}
+
+/// The most liquidity that `amount_0` and `amount_1` can fund for the range
+/// between `sqrt_price_a_x64` and `sqrt_price_b_x64` at the current price.
+pub fn get_liquidity_from_amounts(
+    sqrt_price_current_x64: u128,
+    sqrt_price_a_x64: u128,
+    sqrt_price_b_x64: u128,
+    amount_0: u64,
+    amount_1: u64,
+) -> Result<u128> {
+    let (sqrt_price_a_x64, sqrt_price_b_x64) = ordered(sqrt_price_a_x64, sqrt_price_b_x64);
+    if sqrt_price_current_x64 <= sqrt_price_a_x64 {
+        get_liquidity_from_amount_0(sqrt_price_a_x64, sqrt_price_b_x64, amount_0)
+    } else if sqrt_price_current_x64 < sqrt_price_b_x64 {
+        Ok(
+            get_liquidity_from_amount_0(sqrt_price_current_x64, sqrt_price_b_x64, amount_0)?.min(
+                get_liquidity_from_amount_1(sqrt_price_a_x64, sqrt_price_current_x64, amount_1)?,
+            ),
+        )
+    } else {
+        get_liquidity_from_amount_1(sqrt_price_a_x64, sqrt_price_b_x64, amount_1)
+    }
+}
+
+/// Token 0 needed for `liquidity` between two sqrt prices.
+pub fn get_delta_amount_0_unsigned(
+    sqrt_price_a_x64: u128,
+    sqrt_price_b_x64: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (sqrt_price_a_x64, sqrt_price_b_x64) = ordered(sqrt_price_a_x64, sqrt_price_b_x64);
+    if sqrt_price_a_x64 == 0 {
+        return Err(format_err!("sqrt price must be positive"));
+    }
+    let numerator_1 = U256::from(liquidity) << 64;
+    let numerator_2 = U256::from(sqrt_price_b_x64 - sqrt_price_a_x64);
+    let overflow = || TokenAmountOverflow;
+
+    let result = if round_up {
+        numerator_1
+            .mul_div_ceil(numerator_2, U256::from(sqrt_price_b_x64))
+            .ok_or_else(overflow)?
+            .div_rounding_up(U256::from(sqrt_price_a_x64))
+    } else {
+        numerator_1
+            .mul_div_floor(numerator_2, U256::from(sqrt_price_b_x64))
+            .ok_or_else(overflow)?
+            / U256::from(sqrt_price_a_x64)
+    };
+    to_u64(result)
+}
+
+/// Token 1 needed for `liquidity` between two sqrt prices.
+pub fn get_delta_amount_1_unsigned(
+    sqrt_price_a_x64: u128,
+    sqrt_price_b_x64: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u64> {
+    let (sqrt_price_a_x64, sqrt_price_b_x64) = ordered(sqrt_price_a_x64, sqrt_price_b_x64);
+    let liquidity = U256::from(liquidity);
+    let price_diff = U256::from(sqrt_price_b_x64 - sqrt_price_a_x64);
+    let result = if round_up {
+        liquidity.mul_div_ceil(price_diff, U256::from(Q64))
+    } else {
+        liquidity.mul_div_floor(price_diff, U256::from(Q64))
+    }
+    .ok_or(TokenAmountOverflow)?;
+    to_u64(result)
+}
+
+/// Token amounts that adding (`liquidity_delta > 0`, rounded up) or
+/// removing (`< 0`, rounded down) liquidity in `[tick_lower, tick_upper)`
+/// moves at the current price.
+pub fn get_delta_amounts_signed(
+    tick_current: i32,
+    sqrt_price_x64_current: u128,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity_delta: i128,
+) -> Result<(u64, u64)> {
+    let liquidity = liquidity_delta.unsigned_abs();
+    let round_up = liquidity_delta > 0;
+    let sqrt_price_lower_x64 = get_sqrt_price_at_tick(tick_lower)?;
+    let sqrt_price_upper_x64 = get_sqrt_price_at_tick(tick_upper)?;
+
+    if tick_current < tick_lower {
+        Ok((
+            get_delta_amount_0_unsigned(
+                sqrt_price_lower_x64,
+                sqrt_price_upper_x64,
+                liquidity,
+                round_up,
+            )?,
+            0,
+        ))
+    } else if tick_current < tick_upper {
+        Ok((
+            get_delta_amount_0_unsigned(
+                sqrt_price_x64_current,
+                sqrt_price_upper_x64,
+                liquidity,
+                round_up,
+            )?,
+            get_delta_amount_1_unsigned(
+                sqrt_price_lower_x64,
+                sqrt_price_x64_current,
+                liquidity,
+                round_up,
+            )?,
+        ))
+    } else {
+        Ok((
+            0,
+            get_delta_amount_1_unsigned(
+                sqrt_price_lower_x64,
+                sqrt_price_upper_x64,
+                liquidity,
+                round_up,
+            )?,
+        ))
+    }
+}
+
+/// Applies a signed liquidity change, failing on under- or overflow.
+pub fn add_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    if delta < 0 {
+        liquidity
+            .checked_sub(delta.unsigned_abs())
+            .ok_or_else(|| format_err!("Liquidity underflows"))
+    } else {
+        liquidity
+            .checked_add(delta as u128)
+            .ok_or_else(|| format_err!("Liquidity overflows u128"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICK_LOWER: i32 = -120;
+    const TICK_UPPER: i32 = 180;
+    const LIQUIDITY: i128 = 123_456_789_012;
+
+    fn amounts_at(tick_current: i32, liquidity_delta: i128) -> (u64, u64) {
+        get_delta_amounts_signed(
+            tick_current,
+            get_sqrt_price_at_tick(tick_current).unwrap(),
+            TICK_LOWER,
+            TICK_UPPER,
+            liquidity_delta,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn deposit_rounds_up_and_withdraw_rounds_down_in_range() {
+        assert_eq!(amounts_at(0, LIQUIDITY), (1_106_071_014, 738_486_137));
+        assert_eq!(amounts_at(0, -LIQUIDITY), (1_106_071_013, 738_486_136));
+    }
+
+    #[test]
+    fn range_above_price_takes_only_token_0() {
+        assert_eq!(amounts_at(-200, LIQUIDITY), (1_849_001_164, 0));
+        assert_eq!(amounts_at(-200, -LIQUIDITY), (1_849_001_163, 0));
+    }
+
+    #[test]
+    fn range_below_price_takes_only_token_1() {
+        assert_eq!(amounts_at(200, LIQUIDITY), (0, 1_854_556_218));
+        assert_eq!(amounts_at(200, -LIQUIDITY), (0, 1_854_556_217));
+    }
+
+    #[test]
+    fn liquidity_from_amounts_fits_within_amounts() {
+        let sqrt_price_x64 = get_sqrt_price_at_tick(0).unwrap();
+        let liquidity = get_liquidity_from_amounts(
+            sqrt_price_x64,
+            get_sqrt_price_at_tick(TICK_LOWER).unwrap(),
+            get_sqrt_price_at_tick(TICK_UPPER).unwrap(),
+            1_000_000,
+            2_000_000,
+        )
+        .unwrap();
+        assert_eq!(liquidity, 111_617_416);
+
+        let (amount_0, amount_1) = amounts_at(0, liquidity as i128);
+        assert!(amount_0 <= 1_000_000);
+        assert!(amount_1 <= 2_000_000);
+    }
+
+    #[test]
+    fn empty_range_has_no_liquidity() {
+        let sqrt_price_x64 = get_sqrt_price_at_tick(0).unwrap();
+        assert!(get_liquidity_from_amount_0(sqrt_price_x64, sqrt_price_x64, 1).is_err());
+        assert!(get_liquidity_from_amount_1(sqrt_price_x64, sqrt_price_x64, 1).is_err());
+    }
+
+    #[test]
+    fn add_delta_checks_bounds() {
+        assert_eq!(add_delta(10, -4).unwrap(), 6);
+        assert_eq!(add_delta(10, 4).unwrap(), 14);
+        assert!(add_delta(3, -4).is_err());
+        assert!(add_delta(u128::MAX, 1).is_err());
+    }
+}
@@ -1 +1,29 @@
-pub mod clmm_create_pool;
\ No newline at end of file
+pub mod big_num;
+pub mod clmm_close_position;
+pub mod clmm_collect_remaining_rewards;
+pub mod clmm_collect_rewards;
+pub mod clmm_config;
+pub mod clmm_create_pool;
+pub mod clmm_decrease_liquidity;
+pub mod clmm_increase_liquidity;
+pub mod clmm_initialize_reward;
+pub mod clmm_limit_order;
+pub mod clmm_observation;
+pub mod clmm_open_position;
+pub mod clmm_pool;
+pub mod clmm_position;
+pub mod clmm_position_fees;
+pub mod clmm_price;
+pub mod clmm_quote;
+pub mod clmm_rebalance;
+pub mod clmm_reward;
+pub mod clmm_set_reward_params;
+pub mod clmm_swap;
+pub mod clmm_swap_fixture;
+pub mod clmm_tick_array;
+pub mod clmm_wallet_positions;
+pub mod liquidity_math;
+pub mod sqrt_price_math;
+pub mod swap_math;
+pub mod swap_simulator;
+pub mod tick_math;
\ No newline at end of file
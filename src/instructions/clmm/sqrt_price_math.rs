@@ -0,0 +1,165 @@
+use anyhow::{Result, format_err};
+
+use super::big_num::U256;
+
+/// Next sqrt price after adding (`add`) or removing `amount` of token 0,
+/// rounded up so the price never moves further than the amount allows.
+fn get_next_sqrt_price_from_amount_0_rounding_up(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount: u64,
+    add: bool,
+) -> Result<u128> {
+    if amount == 0 {
+        return Ok(sqrt_price_x64);
+    }
+    let numerator_1 = U256::from(liquidity) << 64;
+    let product = U256::from(amount) * U256::from(sqrt_price_x64);
+    let overflow = || format_err!("sqrt price overflows u128");
+
+    let next = if add {
+        match numerator_1.checked_add(product) {
+            Some(denominator) => numerator_1
+                .mul_div_ceil(U256::from(sqrt_price_x64), denominator)
+                .ok_or_else(overflow)?,
+            None => numerator_1.div_rounding_up(
+                (numerator_1 / U256::from(sqrt_price_x64))
+                    .checked_add(U256::from(amount))
+                    .ok_or_else(overflow)?,
+            ),
+        }
+    } else {
+        if numerator_1 <= product {
+            return Err(format_err!("Not enough token 0 liquidity for the swap"));
+        }
+        numerator_1
+            .mul_div_ceil(U256::from(sqrt_price_x64), numerator_1 - product)
+            .ok_or_else(overflow)?
+    };
+    if next > U256::from(u128::MAX) {
+        return Err(overflow());
+    }
+    Ok(next.as_u128())
+}
+
+/// Next sqrt price after adding (`add`) or removing `amount` of token 1,
+/// rounded down so the price never moves further than the amount allows.
+fn get_next_sqrt_price_from_amount_1_rounding_down(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount: u64,
+    add: bool,
+) -> Result<u128> {
+    let amount_x64 = U256::from(amount) << 64;
+    if add {
+        let quotient = amount_x64 / U256::from(liquidity);
+        let next = U256::from(sqrt_price_x64) + quotient;
+        if next > U256::from(u128::MAX) {
+            return Err(format_err!("sqrt price overflows u128"));
+        }
+        Ok(next.as_u128())
+    } else {
+        let quotient = amount_x64.div_rounding_up(U256::from(liquidity));
+        if U256::from(sqrt_price_x64) <= quotient {
+            return Err(format_err!("Not enough token 1 liquidity for the swap"));
+        }
+        Ok(sqrt_price_x64 - quotient.as_u128())
+    }
+}
+
+/// Sqrt price after `amount_in` enters the pool.
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Result<u128> {
+    if sqrt_price_x64 == 0 || liquidity == 0 {
+        return Err(format_err!(
+            "Pool has no price or liquidity to swap against"
+        ));
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64, liquidity, amount_in, true)
+    }
+}
+
+/// Sqrt price after `amount_out` leaves the pool.
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_out: u64,
+    zero_for_one: bool,
+) -> Result<u128> {
+    if sqrt_price_x64 == 0 || liquidity == 0 {
+        return Err(format_err!(
+            "Pool has no price or liquidity to swap against"
+        ));
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_1_rounding_down(
+            sqrt_price_x64,
+            liquidity,
+            amount_out,
+            false,
+        )
+    } else {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_out, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQRT_PRICE_X64: u128 = 1 << 64;
+    const LIQUIDITY: u128 = 1_000_000_000_000;
+
+    #[test]
+    fn input_moves_price_towards_output_token() {
+        assert_eq!(
+            get_next_sqrt_price_from_input(SQRT_PRICE_X64, LIQUIDITY, 1_000_000, true).unwrap(),
+            18446725626983924633
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(SQRT_PRICE_X64, LIQUIDITY, 1_000_000, false).unwrap(),
+            18446762520453625325
+        );
+    }
+
+    #[test]
+    fn output_moves_price_further_than_input() {
+        assert_eq!(
+            get_next_sqrt_price_from_output(SQRT_PRICE_X64, LIQUIDITY, 1_000_000, true).unwrap(),
+            18446725626965477906
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_output(SQRT_PRICE_X64, LIQUIDITY, 1_000_000, false).unwrap(),
+            18446762520472072089
+        );
+    }
+
+    #[test]
+    fn zero_amount_leaves_price_unchanged() {
+        for zero_for_one in [true, false] {
+            assert_eq!(
+                get_next_sqrt_price_from_input(SQRT_PRICE_X64, LIQUIDITY, 0, zero_for_one).unwrap(),
+                SQRT_PRICE_X64
+            );
+        }
+    }
+
+    #[test]
+    fn output_beyond_liquidity_is_rejected() {
+        assert!(get_next_sqrt_price_from_output(SQRT_PRICE_X64, 1, u64::MAX, true).is_err());
+        assert!(get_next_sqrt_price_from_output(SQRT_PRICE_X64, 1, u64::MAX, false).is_err());
+    }
+
+    #[test]
+    fn empty_pool_is_rejected() {
+        assert!(get_next_sqrt_price_from_input(SQRT_PRICE_X64, 0, 1, true).is_err());
+        assert!(get_next_sqrt_price_from_output(0, LIQUIDITY, 1, false).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::{Result, format_err};
+
+use super::big_num::U256;
+use super::clmm_config::FEE_RATE_DENOMINATOR_VALUE;
+use super::liquidity_math::{
+    TokenAmountOverflow, get_delta_amount_0_unsigned, get_delta_amount_1_unsigned,
+};
+use super::sqrt_price_math::{get_next_sqrt_price_from_input, get_next_sqrt_price_from_output};
+
+/// Outcome of swapping within a single range of constant liquidity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Token amount needed to move between two sqrt prices, or `None` when it
+/// would not fit in a `u64`, in which case the target is out of reach. Any
+/// other failure is an error, as in the program.
+fn amount_in_range(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    zero_for_one: bool,
+    is_base_input: bool,
+) -> Result<Option<u64>> {
+    let amount = match (zero_for_one, is_base_input) {
+        (true, true) => get_delta_amount_0_unsigned(
+            sqrt_price_target_x64,
+            sqrt_price_current_x64,
+            liquidity,
+            true,
+        ),
+        (false, true) => get_delta_amount_1_unsigned(
+            sqrt_price_current_x64,
+            sqrt_price_target_x64,
+            liquidity,
+            true,
+        ),
+        (true, false) => get_delta_amount_1_unsigned(
+            sqrt_price_target_x64,
+            sqrt_price_current_x64,
+            liquidity,
+            false,
+        ),
+        (false, false) => get_delta_amount_0_unsigned(
+            sqrt_price_current_x64,
+            sqrt_price_target_x64,
+            liquidity,
+            false,
+        ),
+    };
+    match amount {
+        Ok(amount) => Ok(Some(amount)),
+        Err(error) if error.is::<TokenAmountOverflow>() => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Swaps as much of `amount_remaining` as fits before the price reaches
+/// `sqrt_price_target_x64`, charging `fee_rate` (out of 1,000,000) on the
+/// input. Mirrors the program's `compute_swap_step`, rounding included.
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u32,
+    is_base_input: bool,
+    zero_for_one: bool,
+) -> Result<SwapStep> {
+    let fee_denominator = FEE_RATE_DENOMINATOR_VALUE as u64;
+    let mut step = SwapStep::default();
+
+    let amount_to_target = amount_in_range(
+        sqrt_price_current_x64,
+        sqrt_price_target_x64,
+        liquidity,
+        zero_for_one,
+        is_base_input,
+    )?;
+    let amount_usable = if is_base_input {
+        (amount_remaining as u128 * (fee_denominator - fee_rate as u64) as u128
+            / fee_denominator as u128) as u64
+    } else {
+        amount_remaining
+    };
+    step.sqrt_price_next_x64 = match amount_to_target {
+        Some(amount) if amount_usable >= amount => sqrt_price_target_x64,
+        _ if is_base_input => get_next_sqrt_price_from_input(
+            sqrt_price_current_x64,
+            liquidity,
+            amount_usable,
+            zero_for_one,
+        )?,
+        _ => get_next_sqrt_price_from_output(
+            sqrt_price_current_x64,
+            liquidity,
+            amount_usable,
+            zero_for_one,
+        )?,
+    };
+    let reached_target = step.sqrt_price_next_x64 == sqrt_price_target_x64;
+
+    if zero_for_one {
+        step.amount_in = match amount_to_target {
+            Some(amount) if reached_target && is_base_input => amount,
+            _ => get_delta_amount_0_unsigned(
+                step.sqrt_price_next_x64,
+                sqrt_price_current_x64,
+                liquidity,
+                true,
+            )?,
+        };
+        step.amount_out = match amount_to_target {
+            Some(amount) if reached_target && !is_base_input => amount,
+            _ => get_delta_amount_1_unsigned(
+                step.sqrt_price_next_x64,
+                sqrt_price_current_x64,
+                liquidity,
+                false,
+            )?,
+        };
+    } else {
+        step.amount_in = match amount_to_target {
+            Some(amount) if reached_target && is_base_input => amount,
+            _ => get_delta_amount_1_unsigned(
+                sqrt_price_current_x64,
+                step.sqrt_price_next_x64,
+                liquidity,
+                true,
+            )?,
+        };
+        step.amount_out = match amount_to_target {
+            Some(amount) if reached_target && !is_base_input => amount,
+            _ => get_delta_amount_0_unsigned(
+                sqrt_price_current_x64,
+                step.sqrt_price_next_x64,
+                liquidity,
+                false,
+            )?,
+        };
+    }
+
+    if !is_base_input && step.amount_out > amount_remaining {
+        step.amount_out = amount_remaining;
+    }
+
+    step.fee_amount = if is_base_input && !reached_target {
+        // Whatever is left of the input once the price stops is all fee.
+        amount_remaining - step.amount_in
+    } else {
+        let fee = U256::from(step.amount_in)
+            .mul_div_ceil(
+                U256::from(fee_rate),
+                U256::from(fee_denominator - fee_rate as u64),
+            )
+            .ok_or_else(|| format_err!("Fee amount overflows"))?;
+        if fee > U256::from(u64::MAX) {
+            return Err(format_err!("Fee amount overflows u64"));
+        }
+        fee.as_u64()
+    };
+
+    Ok(step)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::clmm::tick_math::MIN_SQRT_PRICE_X64;
+
+    #[test]
+    fn unreachable_target_stops_short() {
+        // Reaching the minimum price from 1.0 would take more token 0 than
+        // fits in a u64, so the step runs out of input first.
+        let step = compute_swap_step(
+            1 << 64,
+            MIN_SQRT_PRICE_X64,
+            1 << 80,
+            1_000_000,
+            2_500,
+            true,
+            true,
+        )
+        .unwrap();
+
+        assert!(step.sqrt_price_next_x64 > MIN_SQRT_PRICE_X64);
+        assert!(step.sqrt_price_next_x64 < 1 << 64);
+        assert_eq!(step.amount_in + step.fee_amount, 1_000_000);
+    }
+}
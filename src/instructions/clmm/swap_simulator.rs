@@ -0,0 +1,159 @@
+use anyhow::{Result, format_err};
+
+use super::clmm_config::AmmConfig;
+use super::clmm_pool::PoolState;
+use super::clmm_tick_array::{TickArrayState, get_array_start_index};
+use super::liquidity_math::add_delta;
+use super::swap_math::compute_swap_step;
+use super::tick_math::{MAX_TICK, MIN_TICK, get_sqrt_price_at_tick, get_tick_at_sqrt_price};
+
+/// Outcome of simulating a swap. Amounts are what the pool's vaults change
+/// by, so transfer fees are not included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapSimulation {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Trade fee paid, protocol and fund shares included.
+    pub fee_amount: u64,
+    /// Part of the specified amount left unswapped because the price
+    /// reached `sqrt_price_limit_x64`.
+    pub amount_remaining: u64,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    /// Start indexes of the tick arrays the swap read, in order.
+    pub tick_arrays_touched: Vec<i32>,
+}
+
+/// Simulates a swap as the program's `swap_v2` would execute it, walking
+/// `tick_arrays` (the pool's initialised tick arrays in the swap direction,
+/// in order) and crossing initialised ticks. `amount_specified` is the input
+/// when `is_base_input` and the output otherwise, net of transfer fees.
+pub fn simulate_swap(
+    pool_state: &PoolState,
+    amm_config: &AmmConfig,
+    tick_arrays: &[TickArrayState],
+    zero_for_one: bool,
+    amount_specified: u64,
+    sqrt_price_limit_x64: u128,
+    is_base_input: bool,
+) -> Result<SwapSimulation> {
+    if amount_specified == 0 {
+        return Err(format_err!("Swap amount must be positive"));
+    }
+    let tick_spacing = pool_state.tick_spacing;
+    let mut tick_arrays = tick_arrays.iter();
+    let mut tick_array = tick_arrays
+        .next()
+        .ok_or_else(|| format_err!("At least one tick array is required"))?;
+
+    let mut simulation = SwapSimulation {
+        amount_in: 0,
+        amount_out: 0,
+        fee_amount: 0,
+        amount_remaining: amount_specified,
+        sqrt_price_x64: pool_state.sqrt_price_x64,
+        tick_current: pool_state.tick_current,
+        liquidity: pool_state.liquidity,
+        tick_arrays_touched: vec![tick_array.start_tick_index],
+    };
+    // When the current tick's array is uninitialised the swap starts at the
+    // first initialised tick of the next array instead.
+    let mut in_current_tick_array =
+        tick_array.start_tick_index == get_array_start_index(pool_state.tick_current, tick_spacing);
+
+    while simulation.amount_remaining != 0 && simulation.sqrt_price_x64 != sqrt_price_limit_x64 {
+        let sqrt_price_start_x64 = simulation.sqrt_price_x64;
+
+        let mut next_tick = if in_current_tick_array {
+            tick_array.next_initialized_tick(simulation.tick_current, tick_spacing, zero_for_one)
+        } else {
+            in_current_tick_array = true;
+            tick_array.first_initialized_tick(zero_for_one)
+        };
+        if next_tick.is_none() {
+            tick_array = tick_arrays.next().ok_or_else(|| {
+                format_err!(
+                    "Swap needs more than the {} tick arrays provided",
+                    simulation.tick_arrays_touched.len()
+                )
+            })?;
+            simulation
+                .tick_arrays_touched
+                .push(tick_array.start_tick_index);
+            next_tick = tick_array.first_initialized_tick(zero_for_one);
+        }
+        let next_tick = *next_tick.ok_or_else(|| {
+            format_err!(
+                "Tick array starting at {} has no initialised tick",
+                tick_array.start_tick_index
+            )
+        })?;
+
+        let tick_next = next_tick.tick.clamp(MIN_TICK, MAX_TICK);
+        let sqrt_price_next_x64 = get_sqrt_price_at_tick(tick_next)?;
+        let sqrt_price_target_x64 = if zero_for_one {
+            sqrt_price_next_x64.max(sqrt_price_limit_x64)
+        } else {
+            sqrt_price_next_x64.min(sqrt_price_limit_x64)
+        };
+
+        let step = compute_swap_step(
+            simulation.sqrt_price_x64,
+            sqrt_price_target_x64,
+            simulation.liquidity,
+            simulation.amount_remaining,
+            amm_config.trade_fee_rate,
+            is_base_input,
+            zero_for_one,
+        )?;
+        simulation.sqrt_price_x64 = step.sqrt_price_next_x64;
+
+        let step_amount_in = step
+            .amount_in
+            .checked_add(step.fee_amount)
+            .ok_or_else(|| format_err!("Swap input overflows u64"))?;
+        let overflow = || format_err!("Swap amounts overflow u64");
+        let consumed = if is_base_input {
+            step_amount_in
+        } else {
+            step.amount_out
+        };
+        simulation.amount_remaining = simulation
+            .amount_remaining
+            .checked_sub(consumed)
+            .ok_or_else(overflow)?;
+        simulation.amount_in = simulation
+            .amount_in
+            .checked_add(step_amount_in)
+            .ok_or_else(overflow)?;
+        simulation.amount_out = simulation
+            .amount_out
+            .checked_add(step.amount_out)
+            .ok_or_else(overflow)?;
+        simulation.fee_amount = simulation
+            .fee_amount
+            .checked_add(step.fee_amount)
+            .ok_or_else(overflow)?;
+
+        if simulation.sqrt_price_x64 == sqrt_price_next_x64 {
+            if next_tick.is_initialized() {
+                let liquidity_net = if zero_for_one {
+                    -next_tick.liquidity_net
+                } else {
+                    next_tick.liquidity_net
+                };
+                simulation.liquidity = add_delta(simulation.liquidity, liquidity_net)?;
+            }
+            simulation.tick_current = if zero_for_one {
+                tick_next - 1
+            } else {
+                tick_next
+            };
+        } else if simulation.sqrt_price_x64 != sqrt_price_start_x64 {
+            simulation.tick_current = get_tick_at_sqrt_price(simulation.sqrt_price_x64)?;
+        }
+    }
+
+    Ok(simulation)
+}
@@ -0,0 +1,219 @@
+use anyhow::{Result, format_err};
+
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = -MIN_TICK;
+
+/// `get_sqrt_price_at_tick(MIN_TICK)` and `get_sqrt_price_at_tick(MAX_TICK)`.
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+
+const BIT_PRECISION: u32 = 16;
+
+/// `2^64 / sqrt(1.0001)^(2^i)` for each bit `i` of a tick.
+const TICK_BIT_RATIOS_X64: [u128; 19] = [
+    0xfffcb933bd6fb800,
+    0xfff97272373d4000,
+    0xfff2e50f5f657000,
+    0xffe5caca7e10f000,
+    0xffcb9843d60f7000,
+    0xff973b41fa98e800,
+    0xff2ea16466c9b000,
+    0xfe5dee046a9a3800,
+    0xfcbe86c7900bb000,
+    0xf987a7253ac65800,
+    0xf3392b0822bb6000,
+    0xe7159475a2caf000,
+    0xd097f3bdfd2f2000,
+    0xa9f746462d9f8000,
+    0x70d869a156f31c00,
+    0x31be135f97ed3200,
+    0x9aa508b5b85a500,
+    0x5d6af8dedc582c,
+    0x2216e584f5fa,
+];
+
+/// `sqrt(1.0001^tick)` as a Q64.64 number, bit for bit as the program
+/// computes it.
+pub fn get_sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        return Err(format_err!(
+            "Tick {} is outside [{}, {}]",
+            tick,
+            MIN_TICK,
+            MAX_TICK
+        ));
+    }
+
+    let mut ratio: u128 = if abs_tick & 0x1 != 0 {
+        TICK_BIT_RATIOS_X64[0]
+    } else {
+        1 << 64
+    };
+    for (bit, bit_ratio) in TICK_BIT_RATIOS_X64.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * bit_ratio) >> 64;
+        }
+    }
+
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+
+    Ok(ratio)
+}
+
+/// The greatest tick whose sqrt price is at most `sqrt_price_x64`.
+pub fn get_tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
+    if !(MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        return Err(format_err!(
+            "sqrt_price_x64 {} is outside [{}, {})",
+            sqrt_price_x64,
+            MIN_SQRT_PRICE_X64,
+            MAX_SQRT_PRICE_X64
+        ));
+    }
+
+    // Integer part of log2(sqrt_price), in Q32.32.
+    let msb = 128 - sqrt_price_x64.leading_zeros() - 1;
+    let log2p_integer_x32 = (msb as i128 - 64) << 32;
+
+    // Fractional part, one bit per squaring of the normalised mantissa.
+    let mut bit: i128 = 0x8000_0000_0000_0000;
+    let mut precision = 0;
+    let mut log2p_fraction_x64 = 0;
+    let mut r = if msb >= 64 {
+        sqrt_price_x64 >> (msb - 63)
+    } else {
+        sqrt_price_x64 << (63 - msb)
+    };
+    while bit > 0 && precision < BIT_PRECISION {
+        r *= r;
+        let is_r_more_than_two = r >> 127;
+        r >>= 63 + is_r_more_than_two;
+        log2p_fraction_x64 += bit * is_r_more_than_two as i128;
+        bit >>= 1;
+        precision += 1;
+    }
+    let log2p_x32 = log2p_integer_x32 + (log2p_fraction_x64 >> 32);
+
+    // Change of base to log_sqrt(1.0001), then bracket the estimate's error.
+    let log_sqrt_10001_x64 = log2p_x32 * 59543866431248i128;
+    let tick_low = ((log_sqrt_10001_x64 - 184467440737095516i128) >> 64) as i32;
+    let tick_high = ((log_sqrt_10001_x64 + 15793534762490258745i128) >> 64) as i32;
+
+    Ok(
+        if tick_low == tick_high || get_sqrt_price_at_tick(tick_high)? > sqrt_price_x64 {
+            tick_low
+        } else {
+            tick_high
+        },
+    )
+}
+
+/// Rounds `tick` down to a multiple of `tick_spacing`, staying within the
+/// range of ticks a position can use.
+pub fn floor_tick_to_spacing(tick: i32, tick_spacing: u16) -> i32 {
+    let tick_spacing = tick_spacing as i32;
+    let min_usable_tick = -(MAX_TICK / tick_spacing * tick_spacing);
+    (tick.div_euclid(tick_spacing) * tick_spacing).max(min_usable_tick)
+}
+
+/// Rounds `tick` up to a multiple of `tick_spacing`, staying within the
+/// range of ticks a position can use.
+pub fn ceil_tick_to_spacing(tick: i32, tick_spacing: u16) -> i32 {
+    let tick_spacing = tick_spacing as i32;
+    let max_usable_tick = MAX_TICK / tick_spacing * tick_spacing;
+    (-(-tick).div_euclid(tick_spacing) * tick_spacing).min(max_usable_tick)
+}
+
+/// A position's `[tick_lower, tick_upper)` range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickRange {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqrt_price_at_known_ticks() {
+        assert_eq!(
+            get_sqrt_price_at_tick(MIN_TICK).unwrap(),
+            MIN_SQRT_PRICE_X64
+        );
+        assert_eq!(
+            get_sqrt_price_at_tick(MAX_TICK).unwrap(),
+            MAX_SQRT_PRICE_X64
+        );
+        assert_eq!(get_sqrt_price_at_tick(0).unwrap(), 1 << 64);
+        assert_eq!(get_sqrt_price_at_tick(1).unwrap(), 18447666387855957090);
+        assert_eq!(get_sqrt_price_at_tick(-1).unwrap(), 18445821805675395072);
+    }
+
+    #[test]
+    fn sqrt_price_rejects_ticks_out_of_range() {
+        assert!(get_sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(get_sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    }
+
+    #[test]
+    fn sqrt_price_is_close_to_exact() {
+        for tick in [MIN_TICK, -100_000, -100, -1, 1, 100, 100_000, MAX_TICK] {
+            let exact = 1.0001f64.powi(tick).sqrt() * (1u128 << 64) as f64;
+            let actual = get_sqrt_price_at_tick(tick).unwrap() as f64;
+            assert!(
+                ((actual - exact) / exact).abs() < 1e-9,
+                "tick {}: {} vs {}",
+                tick,
+                actual,
+                exact
+            );
+        }
+    }
+
+    #[test]
+    fn sqrt_price_increases_with_tick() {
+        let mut previous = get_sqrt_price_at_tick(MIN_TICK).unwrap();
+        for tick in MIN_TICK + 1..=MAX_TICK {
+            let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+            assert!(sqrt_price_x64 > previous, "tick {}", tick);
+            previous = sqrt_price_x64;
+        }
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_round_trips() {
+        for tick in (MIN_TICK..MAX_TICK)
+            .step_by(97)
+            .chain([MAX_TICK - 1, -1, 0, 1])
+        {
+            let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price_x64).unwrap(), tick);
+            if tick > MIN_TICK {
+                assert_eq!(
+                    get_tick_at_sqrt_price(sqrt_price_x64 - 1).unwrap(),
+                    tick - 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tick_at_sqrt_price_rejects_prices_out_of_range() {
+        assert!(get_tick_at_sqrt_price(MIN_SQRT_PRICE_X64 - 1).is_err());
+        assert!(get_tick_at_sqrt_price(MAX_SQRT_PRICE_X64).is_err());
+    }
+
+    #[test]
+    fn ticks_round_to_spacing() {
+        assert_eq!(floor_tick_to_spacing(-1, 60), -60);
+        assert_eq!(ceil_tick_to_spacing(-1, 60), 0);
+        assert_eq!(floor_tick_to_spacing(61, 60), 60);
+        assert_eq!(ceil_tick_to_spacing(61, 60), 120);
+        assert_eq!(floor_tick_to_spacing(MIN_TICK, 60), -443580);
+        assert_eq!(ceil_tick_to_spacing(MAX_TICK, 60), 443580);
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{Result, format_err};
+use borsh::BorshDeserialize;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::RpcProgramAccountsConfig,
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use super::cp_amm_pool::RAYDIUM_CP_SWAP_PROGRAM_ID;
+
+pub const FEE_RATE_DENOMINATOR_VALUE: u64 = 1_000_000;
+
+pub const AMM_CONFIG_SEED: &[u8] = b"amm_config";
+
+const AMM_CONFIG_DISCRIMINATOR: [u8; 8] = [218, 244, 33, 104, 203, 203, 43, 111];
+const AMM_CONFIG_ACCOUNT_LEN: u64 = 8 + 228;
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct AmmConfig {
+    pub bump: u8,
+    pub disable_create_pool: bool,
+    pub index: u16,
+    pub trade_fee_rate: u64,
+    pub protocol_fee_rate: u64,
+    pub fund_fee_rate: u64,
+    pub create_pool_fee: u64,
+    pub protocol_owner: Pubkey,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 16],
+}
+
+impl AmmConfig {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != AMM_CONFIG_DISCRIMINATOR {
+            return Err(format_err!("Account is not a CP-AMM AmmConfig"));
+        }
+        Ok(AmmConfig::deserialize(&mut &data[8..])?)
+    }
+}
+
+/// How pool creation should pick its fee tier.
+#[derive(Debug, Clone, Copy)]
+pub enum AmmConfigSelector {
+    Index(u16),
+    TradeFeeRate(u64),
+}
+
+pub fn amm_config_address(program_id: &Pubkey, index: u16) -> Pubkey {
+    let (amm_config, _) =
+        Pubkey::find_program_address(&[AMM_CONFIG_SEED, &index.to_le_bytes()], program_id);
+    amm_config
+}
+
+pub fn fetch_amm_config(rpc_client: &RpcClient, amm_config: &Pubkey) -> Result<AmmConfig> {
+    let data = rpc_client.get_account_data(amm_config)?;
+    AmmConfig::from_account_data(&data)
+}
+
+pub fn fetch_amm_config_by_index(
+    rpc_client: &RpcClient,
+    index: u16,
+) -> Result<(Pubkey, AmmConfig)> {
+    let program_id = Pubkey::from_str(RAYDIUM_CP_SWAP_PROGRAM_ID)?;
+    let address = amm_config_address(&program_id, index);
+    let amm_config = fetch_amm_config(rpc_client, &address)?;
+    Ok((address, amm_config))
+}
+
+/// Lists every AmmConfig owned by the CP-AMM program, sorted by index.
+pub fn fetch_all_amm_configs(rpc_client: &RpcClient) -> Result<Vec<(Pubkey, AmmConfig)>> {
+    let program_id = Pubkey::from_str(RAYDIUM_CP_SWAP_PROGRAM_ID)?;
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(AMM_CONFIG_ACCOUNT_LEN),
+            RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, AMM_CONFIG_DISCRIMINATOR.to_vec())),
+        ]),
+        ..Default::default()
+    };
+
+    let mut amm_configs = rpc_client
+        .get_program_accounts_with_config(&program_id, config)?
+        .into_iter()
+        .map(|(address, account)| Ok((address, AmmConfig::from_account_data(&account.data)?)))
+        .collect::<Result<Vec<_>>>()?;
+    amm_configs.sort_by_key(|(_, amm_config)| amm_config.index);
+
+    Ok(amm_configs)
+}
+
+pub fn resolve_amm_config(
+    rpc_client: &RpcClient,
+    selector: AmmConfigSelector,
+) -> Result<(Pubkey, AmmConfig)> {
+    match selector {
+        AmmConfigSelector::Index(index) => fetch_amm_config_by_index(rpc_client, index),
+        AmmConfigSelector::TradeFeeRate(trade_fee_rate) => fetch_all_amm_configs(rpc_client)?
+            .into_iter()
+            .find(|(_, amm_config)| {
+                amm_config.trade_fee_rate == trade_fee_rate && !amm_config.disable_create_pool
+            })
+            .ok_or_else(|| {
+                format_err!(
+                    "No AmmConfig with trade fee rate {} accepts new pools",
+                    trade_fee_rate
+                )
+            }),
+    }
+}
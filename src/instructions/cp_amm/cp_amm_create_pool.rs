@@ -4,29 +4,33 @@ use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
-    system_program, sysvar,
+    signature::{Signature, Signer},
+    sysvar,
     transaction::Transaction,
 };
-use spl_associated_token_account::get_associated_token_address;
+use solana_system_interface::program as system_program;
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
 use std::str::FromStr;
 use std::time::SystemTime;
 
-pub fn cp_amm_create_pool() -> Result<()> {
-
-    const RAYDIUM_CP_SWAP_PROGRAM_ID: &str = "CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW";
-    const CREATOR_SECRET_KEY_JSON: &str = r#"[86,238,130,90,23,141,232,132,110,230,236,214,227,119,72,63,117,103,243,211,223,26,222,234,246,236,177,248,136,216,158,11,193,37,28,168,115,125,97,184,5,54,12,59,136,67,70,60,55,200,9,122,232,119,247,226,62,130,155,50,83,164,207,166]"#;
-
-    const TOKEN_A_MINT_STR: &str = "4JERHdTjMWSXYJd4tBuDohyNknYDLL5kWRJyGv9gY8bh";
-    const TOKEN_B_MINT_STR: &str = "FpxYcEJBRUFJ46XAcoVRPNJhWnjEGzUY4rQgErEbnegr";
-
-    const INITIAL_TOKEN_A_AMOUNT: u64 = 1_000_000_000_000;
-    const INITIAL_TOKEN_B_AMOUNT: u64 = 1_000_000_000_000;
-
+use super::cp_amm_config::{AmmConfigSelector, resolve_amm_config};
+use super::cp_amm_pool::{AUTH_SEED, RAYDIUM_CP_SWAP_PROGRAM_ID};
+use crate::utils::token_utils::fetch_mint_token_program;
+
+pub fn cp_amm_create_pool(
+    rpc_client: &RpcClient,
+    creator: &dyn Signer,
+    amm_config_selector: AmmConfigSelector,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    init_amount_a: u64,
+    init_amount_b: u64,
+) -> Result<Signature> {
     const CREATE_POOL_FEE_RECEIVER_ID: &str = "G11FKBRaAkHAKuLCgLM6K6NUc9rTjPAznRCjZifrTQe2";
 
-    const AUTH_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
-    const AMM_CONFIG_SEED: &[u8] = b"amm_config";
     const POOL_SEED: &[u8] = b"pool";
     const POOL_LP_MINT_SEED: &[u8] = b"pool_lp_mint";
     const POOL_VAULT_SEED: &[u8] = b"pool_vault";
@@ -41,48 +45,46 @@ pub fn cp_amm_create_pool() -> Result<()> {
 
     println!("🚀 Starting Raydium CP-AMM Liquidity Pool Creation on Devnet...");
 
-    let rpc_client = RpcClient::new("https://api.devnet.solana.com".to_string());
     let program_id = Pubkey::from_str(RAYDIUM_CP_SWAP_PROGRAM_ID)?;
 
-    let secret_key: Vec<u8> = serde_json::from_str(CREATOR_SECRET_KEY_JSON)?;
-    let creator = Keypair::from_bytes(&secret_key)
-        .map_err(|e| format_err!("Failed to create keypair from secret key bytes: {}", e))?;
     println!("🔑 Creator Wallet: {}", creator.pubkey());
 
-    let token_a_mint = Pubkey::from_str(TOKEN_A_MINT_STR)?;
-    let token_b_mint = Pubkey::from_str(TOKEN_B_MINT_STR)?;
-
     let (token_0_mint, token_1_mint, init_amount_0, init_amount_1) = if token_a_mint < token_b_mint
     {
-        (
-            token_a_mint,
-            token_b_mint,
-            INITIAL_TOKEN_A_AMOUNT,
-            INITIAL_TOKEN_B_AMOUNT,
-        )
+        (*token_a_mint, *token_b_mint, init_amount_a, init_amount_b)
     } else {
-        (
-            token_b_mint,
-            token_a_mint,
-            INITIAL_TOKEN_B_AMOUNT,
-            INITIAL_TOKEN_A_AMOUNT,
-        )
+        (*token_b_mint, *token_a_mint, init_amount_b, init_amount_a)
     };
 
-    println!("   - Token 0 Mint: {}", token_0_mint);
-    println!("   - Token 1 Mint: {}", token_1_mint);
+    let token_0_program = fetch_mint_token_program(rpc_client, &token_0_mint)?;
+    let token_1_program = fetch_mint_token_program(rpc_client, &token_1_mint)?;
+
+    println!(
+        "   - Token 0 Mint: {} (program {})",
+        token_0_mint, token_0_program
+    );
+    println!(
+        "   - Token 1 Mint: {} (program {})",
+        token_1_mint, token_1_program
+    );
 
     println!("\n🔍 Deriving PDAs...");
 
     let (authority_pda, _) = Pubkey::find_program_address(&[AUTH_SEED], &program_id);
     println!("   - Authority PDA: {}", authority_pda);
 
-    let amm_config_index = 0u16;
-    let (amm_config_pda, _) = Pubkey::find_program_address(
-        &[AMM_CONFIG_SEED, &amm_config_index.to_le_bytes()],
-        &program_id,
-    );
+    let (amm_config_pda, amm_config) = resolve_amm_config(rpc_client, amm_config_selector)?;
+    if amm_config.disable_create_pool {
+        return Err(format_err!(
+            "AmmConfig {} does not allow creating pools",
+            amm_config_pda
+        ));
+    }
     println!("   - AmmConfig PDA: {}", amm_config_pda);
+    println!(
+        "   - AmmConfig Index: {}, Trade Fee Rate: {}, Create Pool Fee: {}",
+        amm_config.index, amm_config.trade_fee_rate, amm_config.create_pool_fee
+    );
 
     let (pool_state_pda, _) = Pubkey::find_program_address(
         &[
@@ -125,8 +127,16 @@ pub fn cp_amm_create_pool() -> Result<()> {
 
     println!("\n📋 Assembling Accounts for `initialize` instruction...");
 
-    let creator_token_0_ata = get_associated_token_address(&creator.pubkey(), &token_0_mint);
-    let creator_token_1_ata = get_associated_token_address(&creator.pubkey(), &token_1_mint);
+    let creator_token_0_ata = get_associated_token_address_with_program_id(
+        &creator.pubkey(),
+        &token_0_mint,
+        &token_0_program,
+    );
+    let creator_token_1_ata = get_associated_token_address_with_program_id(
+        &creator.pubkey(),
+        &token_1_mint,
+        &token_1_program,
+    );
     let creator_lp_token_ata = get_associated_token_address(&creator.pubkey(), &lp_mint_pda);
 
     println!("   - Creator Token 0 ATA: {}", creator_token_0_ata);
@@ -150,8 +160,8 @@ pub fn cp_amm_create_pool() -> Result<()> {
         AccountMeta::new(create_pool_fee_pubkey, false),
         AccountMeta::new(observation_state_pda, false),
         AccountMeta::new_readonly(spl_token::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(token_0_program, false),
+        AccountMeta::new_readonly(token_1_program, false),
         AccountMeta::new_readonly(spl_associated_token_account::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
         AccountMeta::new_readonly(sysvar::rent::id(), false),
@@ -182,28 +192,29 @@ pub fn cp_amm_create_pool() -> Result<()> {
         data: data_with_discriminator,
     };
 
+    let create_token_0_ata_ix = create_associated_token_account_idempotent(
+        &creator.pubkey(),
+        &creator.pubkey(),
+        &token_0_mint,
+        &token_0_program,
+    );
+    let create_token_1_ata_ix = create_associated_token_account_idempotent(
+        &creator.pubkey(),
+        &creator.pubkey(),
+        &token_1_mint,
+        &token_1_program,
+    );
+
     let latest_blockhash = rpc_client.get_latest_blockhash()?;
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction],
+        &[create_token_0_ata_ix, create_token_1_ata_ix, instruction],
         Some(&creator.pubkey()),
-        &[&creator],
+        &[creator],
         latest_blockhash,
     );
 
     println!("\n📡 Sending transaction to Solana Devnet...");
-    match rpc_client.send_and_confirm_transaction_with_spinner(&transaction) {
-        Ok(signature) => {
-            println!("\n✅ Transaction successful!");
-            println!("   - Signature: {}", signature);
-            println!(
-                "   - View on Solana Explorer: https://explorer.solana.com/tx/{}?cluster=devnet",
-                signature
-            );
-        }
-        Err(e) => {
-            println!("\n❌ Transaction failed: {}", e);
-        }
-    }
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
 
-    Ok(())
+    Ok(signature)
 }
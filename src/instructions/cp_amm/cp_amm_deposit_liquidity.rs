@@ -1,224 +1,130 @@
-use anyhow::{Result, format_err};
-use borsh::{BorshDeserialize, BorshSerialize};
+use anyhow::Result;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::program_pack::Pack;
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
-    pubkey::Pubkey,
-    signature::{Keypair, Signature, Signer},
+    signature::{Signature, Signer},
     transaction::Transaction,
 };
-use spl_token::state::Account as TokenAccount;
-use std::str::FromStr;
-
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-struct DepositInstructionData {
-    pub lp_token_amount: u64,
-    pub maximum_token_0_amount: u64,
-    pub maximum_token_1_amount: u64,
-}
-
-#[derive(BorshDeserialize, Debug)]
-#[allow(dead_code)]
-struct PoolState {
-    pub amm_config: Pubkey,
-    pub pool_creator: Pubkey,
-    pub token_0_vault: Pubkey,
-    pub token_1_vault: Pubkey,
-    pub lp_mint: Pubkey,
-    pub token_0_mint: Pubkey,
-    pub token_1_mint: Pubkey,
-    pub token_0_program: Pubkey,
-    pub token_1_program: Pubkey,
-    pub observation_key: Pubkey,
-    pub auth_bump: u8,
-    pub status: u8,
-    pub lp_mint_decimals: u8,
-    pub mint_0_decimals: u8,
-    pub mint_1_decimals: u8,
-    pub lp_supply: u64,
-    pub protocol_fees_token_0: u64,
-    pub protocol_fees_token_1: u64,
-    pub fund_fees_token_0: u64,
-    pub fund_fees_token_1: u64,
-    pub open_time: u64,
-    pub recent_epoch: u64,
-    pub padding: [u64; 31],
-}
 
-impl PoolState {
-    fn vault_amount_without_fee(&self, vault_0_balance: u64, vault_1_balance: u64) -> (u64, u64) {
-        (
-            vault_0_balance
-                .checked_sub(self.protocol_fees_token_0 + self.fund_fees_token_0)
-                .unwrap(),
-            vault_1_balance
-                .checked_sub(self.protocol_fees_token_1 + self.fund_fees_token_1)
-                .unwrap(),
-        )
-    }
-}
-
-mod curve_calculator {
-    #[derive(Debug)]
-    pub struct TradingTokenResult {
-        pub token_0_amount: u128,
-        pub token_1_amount: u128,
-    }
-
-    #[derive(PartialEq, Eq)]
-    pub enum RoundDirection {
-        Floor,
-        Ceiling,
-    }
-
-    pub fn lp_tokens_to_trading_tokens(
-        lp_token_amount: u128,
-        lp_token_supply: u128,
-        swap_token_0_amount: u128,
-        swap_token_1_amount: u128,
-        round_direction: RoundDirection,
-    ) -> Option<TradingTokenResult> {
-        if lp_token_supply == 0 {
-            return None;
-        }
-        let mut token_0_amount = lp_token_amount
-            .checked_mul(swap_token_0_amount)?
-            .checked_div(lp_token_supply)?;
-        let mut token_1_amount = lp_token_amount
-            .checked_mul(swap_token_1_amount)?
-            .checked_div(lp_token_supply)?;
-
-        if round_direction == RoundDirection::Ceiling {
-            let token_0_remainder = lp_token_amount
-                .checked_mul(swap_token_0_amount)?
-                .checked_rem(lp_token_supply)?;
-            if token_0_remainder > 0 && token_0_amount > 0 {
-                token_0_amount = token_0_amount.checked_add(1)?;
-            }
-            let token_1_remainder = lp_token_amount
-                .checked_mul(swap_token_1_amount)?
-                .checked_rem(lp_token_supply)?;
-            if token_1_remainder > 0 && token_1_amount > 0 {
-                token_1_amount = token_1_amount.checked_add(1)?;
-            }
-        }
-        Some(TradingTokenResult {
-            token_0_amount,
-            token_1_amount,
-        })
-    }
-}
+use super::cp_amm_pool::CpAmmPool;
+use super::cp_amm_quote::{LiquidityQuote, quote_deposit, quote_deposit_from_token_amounts};
+use super::cp_amm_transfer_fee::fetch_pool_transfer_fees;
+use crate::utils::slippage::Slippage;
 
 pub fn cp_amm_deposit_liquidity(
     rpc_client: &RpcClient,
-    user: &Keypair,
+    user: &dyn Signer,
+    pool: &mut CpAmmPool,
     lp_token_amount_to_deposit: u64,
+    slippage: Slippage,
 ) -> Result<Signature> {
-    let program_id = Pubkey::from_str("CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW")?;
-    let pool_id = Pubkey::from_str("549ozjy4M83ZXxvYNYk9qQgYrwX9FisYLb9JZsXdRWAf")?;
-    let pool_authority = Pubkey::from_str("7rQ1QFNosMkUCuh7Z7fPbTHvh73b68sQYdirycEzJVuw")?;
-    let lp_mint = Pubkey::from_str("3ry7sMLrzKdcXCmK5SQDRdztEe1SxMUZz8fHKRK337z2")?;
-    let token_0_mint = Pubkey::from_str("4JERHdTjMWSXYJd4tBuDohyNknYDLL5kWRJyGv9gY8bh")?;
-    let token_1_mint = Pubkey::from_str("FpxYcEJBRUFJ46XAcoVRPNJhWnjEGzUY4rQgErEbnegr")?;
-    let token_0_vault = Pubkey::from_str("8mxrQjyRsg4KFAoLwwtSSnp8C5ZBJxKDECca9RAefAMK")?;
-    let token_1_vault = Pubkey::from_str("C7yYzfghJyUq3cmqYogfjPywdEeW681zHdujqa1qg4x7")?;
-
-    let user_token_0_ata = Pubkey::from_str("8FAozQVK2S5D6Sdm2Zp1uKj3khfjeteKbWEtv3hJX3pq")?;
-    let user_token_1_ata = Pubkey::from_str("4rovNWExfAhxzwxuV4Et5rM26pg67tfDw27uXBUNPAfd")?;
-    let user_lp_token_ata = Pubkey::from_str("CiekUybiVWscUvuqdwXQXdzpYcjBsq8FNTFyHx4hPdyy")?;
+    pool.ensure_owner(&user.pubkey())?;
+    slippage.validate()?;
 
     println!(
         "Depositing {} LP tokens into pool {}",
-        lp_token_amount_to_deposit, pool_id
+        lp_token_amount_to_deposit, pool.pool_id
     );
 
     println!("Fetching live pool data...");
-    let accounts_to_fetch = vec![pool_id, token_0_vault, token_1_vault];
-    let mut account_data = rpc_client.get_multiple_accounts(&accounts_to_fetch)?;
-
-    let pool_state_data = account_data
-        .remove(0)
-        .ok_or_else(|| format_err!("Pool state account not found"))?
-        .data;
-    let pool_state = PoolState::try_from_slice(&pool_state_data[8..])?;
-
-    let token_0_vault_data = account_data
-        .remove(0)
-        .ok_or_else(|| format_err!("Token 0 vault not found"))?
-        .data;
-    let token_0_vault_state = TokenAccount::unpack(&token_0_vault_data)?;
-
-    let token_1_vault_data = account_data
-        .remove(0)
-        .ok_or_else(|| format_err!("Token 1 vault not found"))?
-        .data;
-    let token_1_vault_state = TokenAccount::unpack(&token_1_vault_data)?;
-
-    let (pool_token_0_balance, pool_token_1_balance) =
-        pool_state.vault_amount_without_fee(token_0_vault_state.amount, token_1_vault_state.amount);
-
-    let required_tokens = curve_calculator::lp_tokens_to_trading_tokens(
-        lp_token_amount_to_deposit as u128,
-        pool_state.lp_supply as u128,
-        pool_token_0_balance as u128,
-        pool_token_1_balance as u128,
-        curve_calculator::RoundDirection::Ceiling,
+    let (pool_token_0_balance, pool_token_1_balance) = pool.refresh(rpc_client)?;
+
+    let transfer_fees = fetch_pool_transfer_fees(rpc_client, &pool.state)?;
+
+    let quote = quote_deposit(
+        lp_token_amount_to_deposit,
+        pool.state.lp_supply,
+        pool_token_0_balance,
+        pool_token_1_balance,
+        &transfer_fees,
+    )?;
+    let (token_0_to_deposit, token_1_to_deposit) = quote.deposit_amounts();
+
+    let maximum_token_0_amount = slippage.max_amount(token_0_to_deposit)?;
+    let maximum_token_1_amount = slippage.max_amount(token_1_to_deposit)?;
+
+    send_deposit(
+        rpc_client,
+        user,
+        pool,
+        &quote,
+        maximum_token_0_amount,
+        maximum_token_1_amount,
     )
-    .ok_or_else(|| format_err!("Failed to calculate required tokens"))?;
+}
 
-    let token_0_to_deposit = required_tokens.token_0_amount as u64;
-    let token_1_to_deposit = required_tokens.token_1_amount as u64;
+/// Deposits the largest LP amount affordable with at most
+/// `maximum_token_0_amount` and `maximum_token_1_amount` leaving the wallet.
+/// Either side may be `None`, in which case it is sized from the other.
+pub fn cp_amm_deposit_liquidity_by_token_amount(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut CpAmmPool,
+    maximum_token_0_amount: Option<u64>,
+    maximum_token_1_amount: Option<u64>,
+    slippage: Slippage,
+) -> Result<Signature> {
+    pool.ensure_owner(&user.pubkey())?;
+    slippage.validate()?;
 
-    const SLIPPAGE_BPS: u64 = 100;
-    let maximum_token_0_amount = token_0_to_deposit + (token_0_to_deposit * SLIPPAGE_BPS / 10000);
-    let maximum_token_1_amount = token_1_to_deposit + (token_1_to_deposit * SLIPPAGE_BPS / 10000);
+    println!(
+        "Depositing up to {:?} token 0 and {:?} token 1 into pool {}",
+        maximum_token_0_amount, maximum_token_1_amount, pool.pool_id
+    );
+
+    println!("Fetching live pool data...");
+    let (pool_token_0_balance, pool_token_1_balance) = pool.refresh(rpc_client)?;
+
+    let transfer_fees = fetch_pool_transfer_fees(rpc_client, &pool.state)?;
+
+    let quote = quote_deposit_from_token_amounts(
+        maximum_token_0_amount
+            .map(|maximum| slippage.quote_within(maximum))
+            .transpose()?,
+        maximum_token_1_amount
+            .map(|maximum| slippage.quote_within(maximum))
+            .transpose()?,
+        pool.state.lp_supply,
+        pool_token_0_balance,
+        pool_token_1_balance,
+        &transfer_fees,
+    )?;
+    let (token_0_to_deposit, token_1_to_deposit) = quote.deposit_amounts();
+    println!("Affordable LP Tokens: {}", quote.lp_token_amount);
+
+    send_deposit(
+        rpc_client,
+        user,
+        pool,
+        &quote,
+        slippage.max_amount_capped(token_0_to_deposit, maximum_token_0_amount)?,
+        slippage.max_amount_capped(token_1_to_deposit, maximum_token_1_amount)?,
+    )
+}
+
+fn send_deposit(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &CpAmmPool,
+    quote: &LiquidityQuote,
+    maximum_token_0_amount: u64,
+    maximum_token_1_amount: u64,
+) -> Result<Signature> {
+    let (token_0_to_deposit, token_1_to_deposit) = quote.deposit_amounts();
 
     println!(
-        "Required Token 0: {}, Max Allowed: {}",
-        token_0_to_deposit, maximum_token_0_amount
+        "Required Token 0: {} (transfer fee {}), Max Allowed: {}",
+        token_0_to_deposit, quote.token_0_transfer_fee, maximum_token_0_amount
     );
     println!(
-        "Required Token 1: {}, Max Allowed: {}",
-        token_1_to_deposit, maximum_token_1_amount
+        "Required Token 1: {} (transfer fee {}), Max Allowed: {}",
+        token_1_to_deposit, quote.token_1_transfer_fee, maximum_token_1_amount
     );
 
-    let instruction_discriminator: [u8; 8] = [242, 35, 198, 137, 82, 225, 242, 182];
-
-    let instruction_data = DepositInstructionData {
-        lp_token_amount: lp_token_amount_to_deposit,
+    let instruction = pool.deposit_instruction(
+        quote.lp_token_amount,
         maximum_token_0_amount,
         maximum_token_1_amount,
-    };
-
-    let mut data_with_discriminator = Vec::with_capacity(8 + 24);
-    data_with_discriminator.extend_from_slice(&instruction_discriminator);
-    data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
-
-    let accounts = vec![
-        AccountMeta::new_readonly(user.pubkey(), true),
-        AccountMeta::new_readonly(pool_authority, false),
-        AccountMeta::new(pool_id, false),
-        AccountMeta::new(user_lp_token_ata, false),
-        AccountMeta::new(user_token_0_ata, false),
-        AccountMeta::new(user_token_1_ata, false),
-        AccountMeta::new(token_0_vault, false),
-        AccountMeta::new(token_1_vault, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-        AccountMeta::new_readonly(
-            Pubkey::from_str("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb")?,
-            false,
-        ),
-        AccountMeta::new_readonly(token_0_mint, false),
-        AccountMeta::new_readonly(token_1_mint, false),
-        AccountMeta::new(lp_mint, false),
-    ];
-
-    let instruction = Instruction {
-        program_id,
-        accounts,
-        data: data_with_discriminator,
-    };
+    )?;
 
     let latest_blockhash = rpc_client.get_latest_blockhash()?;
     let transaction = Transaction::new_signed_with_payer(
@@ -0,0 +1,394 @@
+use anyhow::{Result, format_err};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::state::Account as TokenAccount;
+use std::str::FromStr;
+
+pub const RAYDIUM_CP_SWAP_PROGRAM_ID: &str = "CPMDWBwJDtYax9qW7AyRuVC19Cc4L4Vcy4n2BHAbHkCW";
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+pub const AUTH_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+
+const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+
+const DEPOSIT_DISCRIMINATOR: [u8; 8] = [242, 35, 198, 137, 82, 225, 242, 182];
+const WITHDRAW_DISCRIMINATOR: [u8; 8] = [183, 18, 70, 156, 148, 109, 161, 34];
+const SWAP_BASE_INPUT_DISCRIMINATOR: [u8; 8] = [143, 190, 90, 218, 196, 30, 51, 222];
+const SWAP_BASE_OUTPUT_DISCRIMINATOR: [u8; 8] = [55, 217, 98, 86, 163, 74, 180, 173];
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DepositInstructionData {
+    pub lp_token_amount: u64,
+    pub maximum_token_0_amount: u64,
+    pub maximum_token_1_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct WithdrawInstructionData {
+    pub lp_token_amount: u64,
+    pub minimum_token_0_amount: u64,
+    pub minimum_token_1_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SwapBaseInputInstructionData {
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SwapBaseOutputInstructionData {
+    pub max_amount_in: u64,
+    pub amount_out: u64,
+}
+
+// Mirrors the on-chain layout, so not every field is read.
+#[allow(dead_code)]
+#[derive(BorshDeserialize, Debug, Clone)]
+pub struct PoolState {
+    pub amm_config: Pubkey,
+    pub pool_creator: Pubkey,
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub token_0_program: Pubkey,
+    pub token_1_program: Pubkey,
+    pub observation_key: Pubkey,
+    pub auth_bump: u8,
+    pub status: u8,
+    pub lp_mint_decimals: u8,
+    pub mint_0_decimals: u8,
+    pub mint_1_decimals: u8,
+    pub lp_supply: u64,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    pub open_time: u64,
+    pub recent_epoch: u64,
+    pub padding: [u64; 31],
+}
+
+impl PoolState {
+    pub fn from_account_data(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 || data[..8] != POOL_STATE_DISCRIMINATOR {
+            return Err(format_err!("Account is not a CP-AMM PoolState"));
+        }
+        Ok(PoolState::deserialize(&mut &data[8..])?)
+    }
+
+    /// Vault balances net of the protocol and fund fees held in them.
+    pub fn vault_amount_without_fee(
+        &self,
+        vault_0_balance: u64,
+        vault_1_balance: u64,
+    ) -> Result<(u64, u64)> {
+        let without_fee = |balance: u64, protocol_fees: u64, fund_fees: u64| {
+            protocol_fees
+                .checked_add(fund_fees)
+                .and_then(|fees| balance.checked_sub(fees))
+                .ok_or_else(|| format_err!("Pool fees exceed vault balance"))
+        };
+        Ok((
+            without_fee(
+                vault_0_balance,
+                self.protocol_fees_token_0,
+                self.fund_fees_token_0,
+            )?,
+            without_fee(
+                vault_1_balance,
+                self.protocol_fees_token_1,
+                self.fund_fees_token_1,
+            )?,
+        ))
+    }
+}
+
+/// Reads the base token account state, ignoring any Token-2022 extension data
+/// that follows it.
+pub fn unpack_token_account(data: &[u8]) -> Result<TokenAccount> {
+    if data.len() < TokenAccount::LEN {
+        return Err(format_err!("Token account data too short"));
+    }
+    Ok(TokenAccount::unpack_from_slice(&data[..TokenAccount::LEN])?)
+}
+
+/// Everything needed to build instructions against a single CP-AMM pool for
+/// a given owner, resolved from the on-chain `PoolState`.
+#[derive(Debug, Clone)]
+pub struct CpAmmPool {
+    pub program_id: Pubkey,
+    pub pool_id: Pubkey,
+    pub authority: Pubkey,
+    pub state: PoolState,
+    pub owner: Pubkey,
+    pub owner_token_0_ata: Pubkey,
+    pub owner_token_1_ata: Pubkey,
+    pub owner_lp_token_ata: Pubkey,
+}
+
+impl CpAmmPool {
+    pub fn load(rpc_client: &RpcClient, pool_id: &Pubkey, owner: &Pubkey) -> Result<Self> {
+        let program_id = Pubkey::from_str(RAYDIUM_CP_SWAP_PROGRAM_ID)?;
+
+        let pool_account = rpc_client.get_account(pool_id)?;
+        if pool_account.owner != program_id {
+            return Err(format_err!(
+                "Account {} is not owned by the CP-AMM program",
+                pool_id
+            ));
+        }
+        let state = PoolState::from_account_data(&pool_account.data)?;
+
+        let (authority, _) = Pubkey::find_program_address(&[AUTH_SEED], &program_id);
+
+        let owner_token_0_ata = get_associated_token_address_with_program_id(
+            owner,
+            &state.token_0_mint,
+            &state.token_0_program,
+        );
+        let owner_token_1_ata = get_associated_token_address_with_program_id(
+            owner,
+            &state.token_1_mint,
+            &state.token_1_program,
+        );
+        let owner_lp_token_ata =
+            get_associated_token_address_with_program_id(owner, &state.lp_mint, &spl_token::id());
+
+        Ok(Self {
+            program_id,
+            pool_id: *pool_id,
+            authority,
+            state,
+            owner: *owner,
+            owner_token_0_ata,
+            owner_token_1_ata,
+            owner_lp_token_ata,
+        })
+    }
+
+    pub fn ensure_owner(&self, owner: &Pubkey) -> Result<()> {
+        if self.owner != *owner {
+            return Err(format_err!(
+                "Pool handle was loaded for {}, not {}",
+                self.owner,
+                owner
+            ));
+        }
+        Ok(())
+    }
+
+    /// Re-reads the pool state and both vaults in one round trip, returning
+    /// the vault balances net of protocol and fund fees.
+    pub fn refresh(&mut self, rpc_client: &RpcClient) -> Result<(u64, u64)> {
+        let (vault_0_balance, vault_1_balance) = self.refresh_vault_balances(rpc_client)?;
+        self.state
+            .vault_amount_without_fee(vault_0_balance, vault_1_balance)
+    }
+
+    /// Same as [`CpAmmPool::refresh`], but returns the raw vault balances,
+    /// which still include the accrued protocol and fund fees.
+    pub fn refresh_vault_balances(&mut self, rpc_client: &RpcClient) -> Result<(u64, u64)> {
+        let accounts_to_fetch = vec![
+            self.pool_id,
+            self.state.token_0_vault,
+            self.state.token_1_vault,
+        ];
+        let mut account_data = rpc_client.get_multiple_accounts(&accounts_to_fetch)?;
+
+        let pool_state_data = account_data
+            .remove(0)
+            .ok_or_else(|| format_err!("Pool state account not found"))?
+            .data;
+        self.state = PoolState::from_account_data(&pool_state_data)?;
+
+        let token_0_vault_data = account_data
+            .remove(0)
+            .ok_or_else(|| format_err!("Token 0 vault not found"))?
+            .data;
+        let token_0_vault_state = unpack_token_account(&token_0_vault_data)?;
+
+        let token_1_vault_data = account_data
+            .remove(0)
+            .ok_or_else(|| format_err!("Token 1 vault not found"))?
+            .data;
+        let token_1_vault_state = unpack_token_account(&token_1_vault_data)?;
+
+        Ok((token_0_vault_state.amount, token_1_vault_state.amount))
+    }
+
+    fn liquidity_accounts(&self) -> Result<Vec<AccountMeta>> {
+        Ok(vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new(self.pool_id, false),
+            AccountMeta::new(self.owner_lp_token_ata, false),
+            AccountMeta::new(self.owner_token_0_ata, false),
+            AccountMeta::new(self.owner_token_1_ata, false),
+            AccountMeta::new(self.state.token_0_vault, false),
+            AccountMeta::new(self.state.token_1_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Pubkey::from_str(TOKEN_2022_PROGRAM_ID)?, false),
+            AccountMeta::new_readonly(self.state.token_0_mint, false),
+            AccountMeta::new_readonly(self.state.token_1_mint, false),
+            AccountMeta::new(self.state.lp_mint, false),
+        ])
+    }
+
+    pub fn deposit_instruction(
+        &self,
+        lp_token_amount: u64,
+        maximum_token_0_amount: u64,
+        maximum_token_1_amount: u64,
+    ) -> Result<Instruction> {
+        let instruction_data = DepositInstructionData {
+            lp_token_amount,
+            maximum_token_0_amount,
+            maximum_token_1_amount,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 24);
+        data_with_discriminator.extend_from_slice(&DEPOSIT_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: self.liquidity_accounts()?,
+            data: data_with_discriminator,
+        })
+    }
+
+    pub fn withdraw_instruction(
+        &self,
+        lp_token_amount: u64,
+        minimum_token_0_amount: u64,
+        minimum_token_1_amount: u64,
+    ) -> Result<Instruction> {
+        let instruction_data = WithdrawInstructionData {
+            lp_token_amount,
+            minimum_token_0_amount,
+            minimum_token_1_amount,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 24);
+        data_with_discriminator.extend_from_slice(&WITHDRAW_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        let mut accounts = self.liquidity_accounts()?;
+        accounts.push(AccountMeta::new_readonly(spl_memo::id(), false));
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: data_with_discriminator,
+        })
+    }
+
+    /// Returns `true` when `mint` is the pool's token 0 and `false` when it
+    /// is token 1.
+    pub fn is_token_0(&self, mint: &Pubkey) -> Result<bool> {
+        if *mint == self.state.token_0_mint {
+            Ok(true)
+        } else if *mint == self.state.token_1_mint {
+            Ok(false)
+        } else {
+            Err(format_err!(
+                "Mint {} is not part of pool {}",
+                mint,
+                self.pool_id
+            ))
+        }
+    }
+
+    fn swap_accounts(&self, input_is_token_0: bool) -> Vec<AccountMeta> {
+        let state = &self.state;
+        let token_0 = (
+            self.owner_token_0_ata,
+            state.token_0_vault,
+            state.token_0_program,
+            state.token_0_mint,
+        );
+        let token_1 = (
+            self.owner_token_1_ata,
+            state.token_1_vault,
+            state.token_1_program,
+            state.token_1_mint,
+        );
+        let (
+            (input_ata, input_vault, input_program, input_mint),
+            (output_ata, output_vault, output_program, output_mint),
+        ) = if input_is_token_0 {
+            (token_0, token_1)
+        } else {
+            (token_1, token_0)
+        };
+
+        vec![
+            AccountMeta::new_readonly(self.owner, true),
+            AccountMeta::new_readonly(self.authority, false),
+            AccountMeta::new_readonly(state.amm_config, false),
+            AccountMeta::new(self.pool_id, false),
+            AccountMeta::new(input_ata, false),
+            AccountMeta::new(output_ata, false),
+            AccountMeta::new(input_vault, false),
+            AccountMeta::new(output_vault, false),
+            AccountMeta::new_readonly(input_program, false),
+            AccountMeta::new_readonly(output_program, false),
+            AccountMeta::new_readonly(input_mint, false),
+            AccountMeta::new_readonly(output_mint, false),
+            AccountMeta::new(state.observation_key, false),
+        ]
+    }
+
+    pub fn swap_base_input_instruction(
+        &self,
+        input_is_token_0: bool,
+        amount_in: u64,
+        minimum_amount_out: u64,
+    ) -> Result<Instruction> {
+        let instruction_data = SwapBaseInputInstructionData {
+            amount_in,
+            minimum_amount_out,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 16);
+        data_with_discriminator.extend_from_slice(&SWAP_BASE_INPUT_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: self.swap_accounts(input_is_token_0),
+            data: data_with_discriminator,
+        })
+    }
+
+    pub fn swap_base_output_instruction(
+        &self,
+        input_is_token_0: bool,
+        max_amount_in: u64,
+        amount_out: u64,
+    ) -> Result<Instruction> {
+        let instruction_data = SwapBaseOutputInstructionData {
+            max_amount_in,
+            amount_out,
+        };
+
+        let mut data_with_discriminator = Vec::with_capacity(8 + 16);
+        data_with_discriminator.extend_from_slice(&SWAP_BASE_OUTPUT_DISCRIMINATOR);
+        data_with_discriminator.extend_from_slice(&instruction_data.try_to_vec()?);
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: self.swap_accounts(input_is_token_0),
+            data: data_with_discriminator,
+        })
+    }
+}
@@ -0,0 +1,689 @@
+use anyhow::{Result, format_err};
+use spl_token_2022::extension::transfer_fee::TransferFee;
+
+use super::cp_amm_config::AmmConfig;
+use super::cp_amm_pool::PoolState;
+use super::cp_amm_transfer_fee::{PoolTransferFees, transfer_fee, transfer_inverse_fee};
+use super::curve_calculator::{self, RoundDirection, SwapResult};
+
+const MAX_DEPOSIT_SIZING_ATTEMPTS: usize = 8;
+
+/// Result of quoting a CP-AMM swap entirely offline. Amounts are in base
+/// units; prices are in output tokens per input token, adjusted for the
+/// mints' decimals.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapQuote {
+    pub input_is_token_0: bool,
+    /// Leaves the user's wallet, input transfer fee included.
+    pub amount_in: u64,
+    /// Arrives in the user's wallet, output transfer fee deducted.
+    pub amount_out: u64,
+    pub transfer_fee_in: u64,
+    pub transfer_fee_out: u64,
+    pub trade_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+    /// Part of the trade fee left in the pool for liquidity providers.
+    pub lp_fee: u64,
+    pub spot_price: f64,
+    pub effective_price: f64,
+    /// Fraction by which `effective_price` is worse than `spot_price`,
+    /// trade and transfer fees included.
+    pub price_impact: f64,
+    /// Reserves net of protocol and fund fees after the swap settles.
+    pub post_trade_token_0_reserve: u64,
+    pub post_trade_token_1_reserve: u64,
+}
+
+/// Quotes an exact-in swap against `pool_state`, given the raw vault
+/// balances, rounding exactly as the program's `swap_base_input` does. The
+/// input transfer fee is withheld before the curve and the output transfer
+/// fee after it.
+pub fn quote_swap_base_input(
+    pool_state: &PoolState,
+    amm_config: &AmmConfig,
+    vault_0_balance: u64,
+    vault_1_balance: u64,
+    transfer_fees: &PoolTransferFees,
+    input_is_token_0: bool,
+    amount_in: u64,
+) -> Result<SwapQuote> {
+    let (source_reserve, destination_reserve) = directional_reserves(
+        pool_state,
+        vault_0_balance,
+        vault_1_balance,
+        input_is_token_0,
+    )?;
+    let (fee_in, fee_out) = directional_transfer_fees(transfer_fees, input_is_token_0);
+
+    let transfer_fee_in = transfer_fee(fee_in, amount_in)?;
+    let swap_result = curve_calculator::swap_base_input(
+        (amount_in - transfer_fee_in) as u128,
+        source_reserve as u128,
+        destination_reserve as u128,
+        amm_config.trade_fee_rate,
+    )
+    .ok_or_else(|| format_err!("Failed to calculate swap output"))?;
+
+    let amount_out = u64::try_from(swap_result.destination_amount_swapped)?;
+    let transfer_fee_out = transfer_fee(fee_out, amount_out)?;
+    if amount_out <= transfer_fee_out {
+        return Err(format_err!("Swap of {} would return nothing", amount_in));
+    }
+
+    build_quote(
+        pool_state,
+        amm_config,
+        source_reserve,
+        destination_reserve,
+        input_is_token_0,
+        swap_result,
+        (transfer_fee_in, transfer_fee_out),
+    )
+}
+
+/// Quotes an exact-out swap against `pool_state`, given the raw vault
+/// balances, rounding exactly as the program's `swap_base_output` does.
+/// `amount_out` is what arrives after the output transfer fee, and the
+/// quoted input includes the input transfer fee.
+pub fn quote_swap_base_output(
+    pool_state: &PoolState,
+    amm_config: &AmmConfig,
+    vault_0_balance: u64,
+    vault_1_balance: u64,
+    transfer_fees: &PoolTransferFees,
+    input_is_token_0: bool,
+    amount_out: u64,
+) -> Result<SwapQuote> {
+    let (source_reserve, destination_reserve) = directional_reserves(
+        pool_state,
+        vault_0_balance,
+        vault_1_balance,
+        input_is_token_0,
+    )?;
+    let (fee_in, fee_out) = directional_transfer_fees(transfer_fees, input_is_token_0);
+
+    let transfer_fee_out = transfer_inverse_fee(fee_out, amount_out)?;
+    let amount_out_swapped = amount_out
+        .checked_add(transfer_fee_out)
+        .ok_or_else(|| format_err!("Output amount overflows"))?;
+    if amount_out_swapped >= destination_reserve {
+        return Err(format_err!(
+            "Requested output {} exceeds available reserves {}",
+            amount_out_swapped,
+            destination_reserve
+        ));
+    }
+
+    let swap_result = curve_calculator::swap_base_output(
+        amount_out_swapped as u128,
+        source_reserve as u128,
+        destination_reserve as u128,
+        amm_config.trade_fee_rate,
+    )
+    .ok_or_else(|| format_err!("Failed to calculate swap input"))?;
+    let transfer_fee_in =
+        transfer_inverse_fee(fee_in, u64::try_from(swap_result.source_amount_swapped)?)?;
+
+    build_quote(
+        pool_state,
+        amm_config,
+        source_reserve,
+        destination_reserve,
+        input_is_token_0,
+        swap_result,
+        (transfer_fee_in, transfer_fee_out),
+    )
+}
+
+fn directional_transfer_fees(
+    transfer_fees: &PoolTransferFees,
+    input_is_token_0: bool,
+) -> (Option<&TransferFee>, Option<&TransferFee>) {
+    if input_is_token_0 {
+        (
+            transfer_fees.token_0.as_ref(),
+            transfer_fees.token_1.as_ref(),
+        )
+    } else {
+        (
+            transfer_fees.token_1.as_ref(),
+            transfer_fees.token_0.as_ref(),
+        )
+    }
+}
+
+fn directional_reserves(
+    pool_state: &PoolState,
+    vault_0_balance: u64,
+    vault_1_balance: u64,
+    input_is_token_0: bool,
+) -> Result<(u64, u64)> {
+    let (token_0_reserve, token_1_reserve) =
+        pool_state.vault_amount_without_fee(vault_0_balance, vault_1_balance)?;
+    Ok(if input_is_token_0 {
+        (token_0_reserve, token_1_reserve)
+    } else {
+        (token_1_reserve, token_0_reserve)
+    })
+}
+
+fn build_quote(
+    pool_state: &PoolState,
+    amm_config: &AmmConfig,
+    source_reserve: u64,
+    destination_reserve: u64,
+    input_is_token_0: bool,
+    swap_result: SwapResult,
+    (transfer_fee_in, transfer_fee_out): (u64, u64),
+) -> Result<SwapQuote> {
+    let amount_in = u64::try_from(swap_result.source_amount_swapped)?;
+    let amount_out = u64::try_from(swap_result.destination_amount_swapped)?;
+    let user_amount_in = amount_in
+        .checked_add(transfer_fee_in)
+        .ok_or_else(|| format_err!("Input amount overflows"))?;
+    let user_amount_out = amount_out.saturating_sub(transfer_fee_out);
+    let trade_fee = u64::try_from(swap_result.trade_fee)?;
+
+    let protocol_fee = u64::try_from(
+        curve_calculator::protocol_fee(swap_result.trade_fee, amm_config.protocol_fee_rate)
+            .ok_or_else(|| format_err!("Failed to calculate protocol fee"))?,
+    )?;
+    let fund_fee = u64::try_from(
+        curve_calculator::fund_fee(swap_result.trade_fee, amm_config.fund_fee_rate)
+            .ok_or_else(|| format_err!("Failed to calculate fund fee"))?,
+    )?;
+    let lp_fee = trade_fee
+        .checked_sub(protocol_fee + fund_fee)
+        .ok_or_else(|| format_err!("Protocol and fund fees exceed the trade fee"))?;
+
+    // Protocol and fund fees stay in the vault but are booked outside the
+    // reserves, so only the remainder of the input counts towards the curve.
+    let post_source_reserve = source_reserve
+        .checked_add(amount_in - protocol_fee - fund_fee)
+        .ok_or_else(|| format_err!("Source reserve overflow"))?;
+    let post_destination_reserve = destination_reserve - amount_out;
+
+    let (decimals_in, decimals_out) = if input_is_token_0 {
+        (pool_state.mint_0_decimals, pool_state.mint_1_decimals)
+    } else {
+        (pool_state.mint_1_decimals, pool_state.mint_0_decimals)
+    };
+    let spot_price = ui_price(
+        source_reserve,
+        destination_reserve,
+        decimals_in,
+        decimals_out,
+    );
+    let effective_price = ui_price(user_amount_in, user_amount_out, decimals_in, decimals_out);
+    let price_impact = if spot_price > 0.0 {
+        1.0 - effective_price / spot_price
+    } else {
+        0.0
+    };
+
+    let (post_trade_token_0_reserve, post_trade_token_1_reserve) = if input_is_token_0 {
+        (post_source_reserve, post_destination_reserve)
+    } else {
+        (post_destination_reserve, post_source_reserve)
+    };
+
+    Ok(SwapQuote {
+        input_is_token_0,
+        amount_in: user_amount_in,
+        amount_out: user_amount_out,
+        transfer_fee_in,
+        transfer_fee_out,
+        trade_fee,
+        protocol_fee,
+        fund_fee,
+        lp_fee,
+        spot_price,
+        effective_price,
+        price_impact,
+        post_trade_token_0_reserve,
+        post_trade_token_1_reserve,
+    })
+}
+
+fn ui_price(amount_in: u64, amount_out: u64, decimals_in: u8, decimals_out: u8) -> f64 {
+    if amount_in == 0 {
+        return 0.0;
+    }
+    let ui_in = amount_in as f64 / 10f64.powi(decimals_in as i32);
+    let ui_out = amount_out as f64 / 10f64.powi(decimals_out as i32);
+    ui_out / ui_in
+}
+
+/// Token movements for a deposit or withdrawal of LP tokens. `token_*_amount`
+/// is what the pool's reserves change by; the user sends that plus the
+/// transfer fee on deposits, and receives that minus the fee on withdrawals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    pub lp_token_amount: u64,
+    pub token_0_amount: u64,
+    pub token_1_amount: u64,
+    pub token_0_transfer_fee: u64,
+    pub token_1_transfer_fee: u64,
+}
+
+impl LiquidityQuote {
+    /// Amounts leaving the user's wallet for a deposit.
+    pub fn deposit_amounts(&self) -> (u64, u64) {
+        (
+            self.token_0_amount + self.token_0_transfer_fee,
+            self.token_1_amount + self.token_1_transfer_fee,
+        )
+    }
+
+    /// Amounts arriving in the user's wallet for a withdrawal.
+    pub fn withdraw_amounts(&self) -> (u64, u64) {
+        (
+            self.token_0_amount - self.token_0_transfer_fee,
+            self.token_1_amount - self.token_1_transfer_fee,
+        )
+    }
+}
+
+/// Quotes depositing `lp_token_amount` against reserves already net of
+/// protocol and fund fees, rounding in the pool's favour.
+pub fn quote_deposit(
+    lp_token_amount: u64,
+    lp_supply: u64,
+    token_0_reserve: u64,
+    token_1_reserve: u64,
+    transfer_fees: &PoolTransferFees,
+) -> Result<LiquidityQuote> {
+    let required_tokens = curve_calculator::lp_tokens_to_trading_tokens(
+        lp_token_amount as u128,
+        lp_supply as u128,
+        token_0_reserve as u128,
+        token_1_reserve as u128,
+        RoundDirection::Ceiling,
+    )
+    .ok_or_else(|| format_err!("Failed to calculate required tokens"))?;
+
+    let token_0_amount = u64::try_from(required_tokens.token_0_amount)?;
+    let token_1_amount = u64::try_from(required_tokens.token_1_amount)?;
+
+    Ok(LiquidityQuote {
+        lp_token_amount,
+        token_0_amount,
+        token_1_amount,
+        token_0_transfer_fee: transfer_inverse_fee(transfer_fees.token_0.as_ref(), token_0_amount)?,
+        token_1_transfer_fee: transfer_inverse_fee(transfer_fees.token_1.as_ref(), token_1_amount)?,
+    })
+}
+
+/// Quotes withdrawing `lp_token_amount` against reserves already net of
+/// protocol and fund fees, rounding in the pool's favour.
+pub fn quote_withdraw(
+    lp_token_amount: u64,
+    lp_supply: u64,
+    token_0_reserve: u64,
+    token_1_reserve: u64,
+    transfer_fees: &PoolTransferFees,
+) -> Result<LiquidityQuote> {
+    let expected_tokens = curve_calculator::lp_tokens_to_trading_tokens(
+        lp_token_amount as u128,
+        lp_supply as u128,
+        token_0_reserve as u128,
+        token_1_reserve as u128,
+        RoundDirection::Floor,
+    )
+    .ok_or_else(|| format_err!("Failed to calculate expected tokens"))?;
+
+    let token_0_amount = u64::try_from(expected_tokens.token_0_amount)?;
+    let token_1_amount = u64::try_from(expected_tokens.token_1_amount)?;
+
+    Ok(LiquidityQuote {
+        lp_token_amount,
+        token_0_amount,
+        token_1_amount,
+        token_0_transfer_fee: transfer_fee(transfer_fees.token_0.as_ref(), token_0_amount)?,
+        token_1_transfer_fee: transfer_fee(transfer_fees.token_1.as_ref(), token_1_amount)?,
+    })
+}
+
+/// Quotes the largest deposit whose wallet outflow, transfer fees included,
+/// stays within `maximum_token_0_amount` and `maximum_token_1_amount`. A
+/// `None` side is left unconstrained and sized by the other.
+pub fn quote_deposit_from_token_amounts(
+    maximum_token_0_amount: Option<u64>,
+    maximum_token_1_amount: Option<u64>,
+    lp_supply: u64,
+    token_0_reserve: u64,
+    token_1_reserve: u64,
+    transfer_fees: &PoolTransferFees,
+) -> Result<LiquidityQuote> {
+    if maximum_token_0_amount.is_none() && maximum_token_1_amount.is_none() {
+        return Err(format_err!("At least one token amount must be given"));
+    }
+
+    let arriving_amount = |maximum: Option<u64>, fee: Option<&TransferFee>| -> Result<_> {
+        maximum
+            .map(|maximum| Ok(maximum - transfer_fee(fee, maximum)?))
+            .transpose()
+    };
+    let mut budget_0 = arriving_amount(maximum_token_0_amount, transfer_fees.token_0.as_ref())?;
+    let mut budget_1 = arriving_amount(maximum_token_1_amount, transfer_fees.token_1.as_ref())?;
+
+    // The forward fee used for the budget and the inverse fee charged on the
+    // deposit can differ by a unit, so shrink the budget until the quote fits.
+    for _ in 0..MAX_DEPOSIT_SIZING_ATTEMPTS {
+        let lp_for = |budget: Option<u64>, reserve: u64| -> Result<Option<u64>> {
+            budget
+                .map(|budget| {
+                    let lp = curve_calculator::trading_tokens_to_lp_tokens(
+                        budget as u128,
+                        lp_supply as u128,
+                        reserve as u128,
+                    )
+                    .ok_or_else(|| format_err!("Failed to calculate LP amount"))?;
+                    Ok(u64::try_from(lp)?)
+                })
+                .transpose()
+        };
+        let lp_token_amount = match (
+            lp_for(budget_0, token_0_reserve)?,
+            lp_for(budget_1, token_1_reserve)?,
+        ) {
+            (Some(lp_0), Some(lp_1)) => lp_0.min(lp_1),
+            (Some(lp), None) | (None, Some(lp)) => lp,
+            (None, None) => unreachable!(),
+        };
+        if lp_token_amount == 0 {
+            return Err(format_err!("Token amounts are too small to mint any LP"));
+        }
+
+        let quote = quote_deposit(
+            lp_token_amount,
+            lp_supply,
+            token_0_reserve,
+            token_1_reserve,
+            transfer_fees,
+        )?;
+        let (token_0_amount, token_1_amount) = quote.deposit_amounts();
+        let excess_0 = maximum_token_0_amount.map_or(0, |max| token_0_amount.saturating_sub(max));
+        let excess_1 = maximum_token_1_amount.map_or(0, |max| token_1_amount.saturating_sub(max));
+        if excess_0 == 0 && excess_1 == 0 {
+            return Ok(quote);
+        }
+        budget_0 = budget_0.map(|budget| budget.saturating_sub(excess_0));
+        budget_1 = budget_1.map(|budget| budget.saturating_sub(excess_1));
+    }
+
+    Err(format_err!(
+        "Failed to size a deposit within the given amounts"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::pubkey::Pubkey;
+
+    use super::*;
+
+    const TOKEN_0_RESERVE: u64 = 1_000_000_000;
+    const TOKEN_1_RESERVE: u64 = 2_000_000_000;
+
+    // 0.25% trade fee, of which 12% goes to the protocol and 4% to the fund.
+    fn amm_config() -> AmmConfig {
+        AmmConfig {
+            bump: 255,
+            disable_create_pool: false,
+            index: 0,
+            trade_fee_rate: 2_500,
+            protocol_fee_rate: 120_000,
+            fund_fee_rate: 40_000,
+            create_pool_fee: 0,
+            protocol_owner: Pubkey::default(),
+            fund_owner: Pubkey::default(),
+            padding: [0; 16],
+        }
+    }
+
+    fn pool_state() -> PoolState {
+        PoolState {
+            amm_config: Pubkey::default(),
+            pool_creator: Pubkey::default(),
+            token_0_vault: Pubkey::default(),
+            token_1_vault: Pubkey::default(),
+            lp_mint: Pubkey::default(),
+            token_0_mint: Pubkey::default(),
+            token_1_mint: Pubkey::default(),
+            token_0_program: Pubkey::default(),
+            token_1_program: Pubkey::default(),
+            observation_key: Pubkey::default(),
+            auth_bump: 255,
+            status: 0,
+            lp_mint_decimals: 9,
+            mint_0_decimals: 9,
+            mint_1_decimals: 6,
+            lp_supply: 999_999_937,
+            protocol_fees_token_0: 7_000,
+            protocol_fees_token_1: 12_000,
+            fund_fees_token_0: 3_000,
+            fund_fees_token_1: 8_000,
+            open_time: 0,
+            recent_epoch: 0,
+            padding: [0; 31],
+        }
+    }
+
+    // Vault balances whose reserves, net of the pool's fees, are
+    // TOKEN_0_RESERVE and TOKEN_1_RESERVE.
+    fn vault_balances() -> (u64, u64) {
+        (TOKEN_0_RESERVE + 10_000, TOKEN_1_RESERVE + 20_000)
+    }
+
+    fn one_percent_fee() -> TransferFee {
+        TransferFee {
+            epoch: 0.into(),
+            maximum_fee: u64::MAX.into(),
+            transfer_fee_basis_points: 100.into(),
+        }
+    }
+
+    #[test]
+    fn swap_base_input_rounds_trade_fee_up_and_shares_down() {
+        let (vault_0, vault_1) = vault_balances();
+        let quote = quote_swap_base_input(
+            &pool_state(),
+            &amm_config(),
+            vault_0,
+            vault_1,
+            &PoolTransferFees::default(),
+            true,
+            1_000_001,
+        )
+        .unwrap();
+
+        assert_eq!(quote.amount_in, 1_000_001);
+        assert_eq!(quote.amount_out, 1_993_011);
+        // 2500.0025 rounds up; 300.12 and 100.04 round down.
+        assert_eq!(quote.trade_fee, 2_501);
+        assert_eq!(quote.protocol_fee, 300);
+        assert_eq!(quote.fund_fee, 100);
+        assert_eq!(quote.lp_fee, 2_101);
+        assert_eq!(quote.post_trade_token_0_reserve, 1_000_999_601);
+        assert_eq!(quote.post_trade_token_1_reserve, 1_998_006_989);
+    }
+
+    #[test]
+    fn swap_base_output_rounds_input_up() {
+        let (vault_0, vault_1) = vault_balances();
+        let quote = quote_swap_base_output(
+            &pool_state(),
+            &amm_config(),
+            vault_0,
+            vault_1,
+            &PoolTransferFees::default(),
+            false,
+            3_000_000,
+        )
+        .unwrap();
+
+        assert_eq!(quote.amount_out, 3_000_000);
+        assert_eq!(quote.amount_in, 6_033_138);
+        // 15082.845 rounds up; 1809.96 and 603.32 round down.
+        assert_eq!(quote.trade_fee, 15_083);
+        assert_eq!(quote.protocol_fee, 1_809);
+        assert_eq!(quote.fund_fee, 603);
+    }
+
+    #[test]
+    fn swap_base_input_withholds_input_transfer_fee() {
+        let (vault_0, vault_1) = vault_balances();
+        let transfer_fees = PoolTransferFees {
+            token_0: Some(one_percent_fee()),
+            token_1: None,
+        };
+        let quote = quote_swap_base_input(
+            &pool_state(),
+            &amm_config(),
+            vault_0,
+            vault_1,
+            &transfer_fees,
+            true,
+            1_000_000,
+        )
+        .unwrap();
+
+        assert_eq!(quote.amount_in, 1_000_000);
+        assert_eq!(quote.transfer_fee_in, 10_000);
+        assert_eq!(quote.transfer_fee_out, 0);
+        assert_eq!(quote.trade_fee, 2_475);
+        assert_eq!(quote.amount_out, 1_973_101);
+    }
+
+    #[test]
+    fn swap_base_output_grosses_up_output_transfer_fee() {
+        let (vault_0, vault_1) = vault_balances();
+        let transfer_fees = PoolTransferFees {
+            token_0: Some(one_percent_fee()),
+            token_1: None,
+        };
+        let quote = quote_swap_base_output(
+            &pool_state(),
+            &amm_config(),
+            vault_0,
+            vault_1,
+            &transfer_fees,
+            false,
+            500_000,
+        )
+        .unwrap();
+
+        assert_eq!(quote.amount_out, 500_000);
+        assert_eq!(quote.transfer_fee_out, 5_051);
+        assert_eq!(quote.transfer_fee_in, 0);
+        assert_eq!(quote.amount_in, 1_013_146);
+        assert_eq!(quote.trade_fee, 2_533);
+    }
+
+    #[test]
+    fn swap_base_output_rejects_draining_the_reserve() {
+        let (vault_0, vault_1) = vault_balances();
+        let error = quote_swap_base_output(
+            &pool_state(),
+            &amm_config(),
+            vault_0,
+            vault_1,
+            &PoolTransferFees::default(),
+            false,
+            TOKEN_0_RESERVE,
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("exceeds available reserves"));
+    }
+
+    #[test]
+    fn swap_rejects_fees_above_vault_balance() {
+        let result = quote_swap_base_input(
+            &pool_state(),
+            &amm_config(),
+            9_999,
+            TOKEN_1_RESERVE,
+            &PoolTransferFees::default(),
+            true,
+            1_000,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn deposit_rounds_up_and_withdraw_rounds_down() {
+        let lp_supply = pool_state().lp_supply;
+        let transfer_fees = PoolTransferFees::default();
+
+        let deposit = quote_deposit(
+            123_457,
+            lp_supply,
+            TOKEN_0_RESERVE,
+            TOKEN_1_RESERVE,
+            &transfer_fees,
+        )
+        .unwrap();
+        assert_eq!(deposit.deposit_amounts(), (123_458, 246_915));
+
+        let withdraw = quote_withdraw(
+            123_457,
+            lp_supply,
+            TOKEN_0_RESERVE,
+            TOKEN_1_RESERVE,
+            &transfer_fees,
+        )
+        .unwrap();
+        assert_eq!(withdraw.withdraw_amounts(), (123_457, 246_914));
+    }
+
+    #[test]
+    fn deposit_from_token_amounts_stays_within_maximum() {
+        let lp_supply = pool_state().lp_supply;
+        let transfer_fees = PoolTransferFees {
+            token_0: Some(one_percent_fee()),
+            token_1: None,
+        };
+
+        let quote = quote_deposit_from_token_amounts(
+            Some(1_000_000),
+            None,
+            lp_supply,
+            TOKEN_0_RESERVE,
+            TOKEN_1_RESERVE,
+            &transfer_fees,
+        )
+        .unwrap();
+        assert_eq!(quote.lp_token_amount, 989_999);
+        assert_eq!(quote.token_0_transfer_fee, 10_000);
+        assert_eq!(quote.deposit_amounts(), (1_000_000, 1_979_999));
+
+        // One more LP token would need more than the maximum.
+        let larger = quote_deposit(
+            quote.lp_token_amount + 1,
+            lp_supply,
+            TOKEN_0_RESERVE,
+            TOKEN_1_RESERVE,
+            &transfer_fees,
+        )
+        .unwrap();
+        assert!(larger.deposit_amounts().0 > 1_000_000);
+    }
+
+    #[test]
+    fn deposit_from_token_amounts_requires_an_amount() {
+        let result = quote_deposit_from_token_amounts(
+            None,
+            None,
+            pool_state().lp_supply,
+            TOKEN_0_RESERVE,
+            TOKEN_1_RESERVE,
+            &PoolTransferFees::default(),
+        );
+
+        assert!(result.is_err());
+    }
+}
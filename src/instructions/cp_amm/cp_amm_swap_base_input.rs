@@ -0,0 +1,80 @@
+use anyhow::Result;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+
+use super::cp_amm_config::fetch_amm_config;
+use super::cp_amm_pool::CpAmmPool;
+use super::cp_amm_quote::quote_swap_base_input;
+use super::cp_amm_transfer_fee::fetch_pool_transfer_fees;
+use crate::utils::slippage::Slippage;
+
+pub fn cp_amm_swap_base_input(
+    rpc_client: &RpcClient,
+    user: &dyn Signer,
+    pool: &mut CpAmmPool,
+    input_mint: &Pubkey,
+    amount_in: u64,
+    slippage: Slippage,
+) -> Result<Signature> {
+    pool.ensure_owner(&user.pubkey())?;
+    slippage.validate()?;
+    let input_is_token_0 = pool.is_token_0(input_mint)?;
+
+    println!(
+        "Swapping {} of {} in pool {}",
+        amount_in, input_mint, pool.pool_id
+    );
+
+    println!("Fetching live pool data...");
+    let (vault_0_balance, vault_1_balance) = pool.refresh_vault_balances(rpc_client)?;
+    let amm_config = fetch_amm_config(rpc_client, &pool.state.amm_config)?;
+    let transfer_fees = fetch_pool_transfer_fees(rpc_client, &pool.state)?;
+
+    let quote = quote_swap_base_input(
+        &pool.state,
+        &amm_config,
+        vault_0_balance,
+        vault_1_balance,
+        &transfer_fees,
+        input_is_token_0,
+        amount_in,
+    )?;
+    let amount_out = quote.amount_out;
+
+    let minimum_amount_out = slippage.min_amount(amount_out)?;
+
+    println!(
+        "Trade Fee: {}, Transfer Fees: {} in / {} out, Expected Out: {}, Min Accepted: {}",
+        quote.trade_fee,
+        quote.transfer_fee_in,
+        quote.transfer_fee_out,
+        amount_out,
+        minimum_amount_out
+    );
+
+    println!(
+        "Effective Price: {:.6}, Price Impact: {:.4}%",
+        quote.effective_price,
+        quote.price_impact * 100.0
+    );
+
+    let instruction =
+        pool.swap_base_input_instruction(input_is_token_0, amount_in, minimum_amount_out)?;
+
+    let latest_blockhash = rpc_client.get_latest_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&user.pubkey()),
+        &[user],
+        latest_blockhash,
+    );
+
+    println!("Sending swap transaction...");
+    let signature = rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?;
+
+    Ok(signature)
+}
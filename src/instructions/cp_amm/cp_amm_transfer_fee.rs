@@ -0,0 +1,83 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::{
+    BaseStateWithExtensions, StateWithExtensions,
+    transfer_fee::{MAX_FEE_BASIS_POINTS, TransferFee, TransferFeeConfig},
+};
+use spl_token_2022::state::Mint;
+
+use super::cp_amm_pool::PoolState;
+
+/// Transfer fees in force for a pool's two mints during one epoch. `None`
+/// means the mint has no `TransferFeeConfig` extension.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolTransferFees {
+    pub token_0: Option<TransferFee>,
+    pub token_1: Option<TransferFee>,
+}
+
+/// Reads the transfer fee that applies in `epoch` from raw mint data.
+pub fn epoch_transfer_fee(
+    mint_data: &[u8],
+    mint_owner: &Pubkey,
+    epoch: u64,
+) -> Result<Option<TransferFee>> {
+    if *mint_owner != spl_token_2022::id() {
+        return Ok(None);
+    }
+    let mint = StateWithExtensions::<Mint>::unpack(mint_data)?;
+    Ok(mint
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|config| *config.get_epoch_fee(epoch)))
+}
+
+pub fn fetch_pool_transfer_fees(
+    rpc_client: &RpcClient,
+    pool_state: &PoolState,
+) -> Result<PoolTransferFees> {
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let mut mint_accounts =
+        rpc_client.get_multiple_accounts(&[pool_state.token_0_mint, pool_state.token_1_mint])?;
+
+    let token_0_mint = mint_accounts
+        .remove(0)
+        .ok_or_else(|| format_err!("Token 0 mint not found"))?;
+    let token_1_mint = mint_accounts
+        .remove(0)
+        .ok_or_else(|| format_err!("Token 1 mint not found"))?;
+
+    Ok(PoolTransferFees {
+        token_0: epoch_transfer_fee(&token_0_mint.data, &token_0_mint.owner, epoch)?,
+        token_1: epoch_transfer_fee(&token_1_mint.data, &token_1_mint.owner, epoch)?,
+    })
+}
+
+/// Fee withheld when `pre_fee_amount` is transferred, as the program
+/// computes it for withdrawals.
+pub fn transfer_fee(transfer_fee: Option<&TransferFee>, pre_fee_amount: u64) -> Result<u64> {
+    match transfer_fee {
+        None => Ok(0),
+        Some(fee) => fee
+            .calculate_fee(pre_fee_amount)
+            .ok_or_else(|| format_err!("Failed to calculate transfer fee")),
+    }
+}
+
+/// Extra amount that must be sent so `post_fee_amount` arrives after the
+/// transfer fee, as the program computes it for deposits.
+pub fn transfer_inverse_fee(
+    transfer_fee: Option<&TransferFee>,
+    post_fee_amount: u64,
+) -> Result<u64> {
+    match transfer_fee {
+        None => Ok(0),
+        Some(fee) if u16::from(fee.transfer_fee_basis_points) == MAX_FEE_BASIS_POINTS => {
+            Ok(u64::from(fee.maximum_fee))
+        }
+        Some(fee) => fee
+            .calculate_inverse_fee(post_fee_amount)
+            .ok_or_else(|| format_err!("Failed to calculate inverse transfer fee")),
+    }
+}
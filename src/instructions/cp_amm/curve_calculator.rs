@@ -0,0 +1,161 @@
+use super::cp_amm_config::FEE_RATE_DENOMINATOR_VALUE;
+
+#[derive(Debug)]
+pub struct TradingTokenResult {
+    pub token_0_amount: u128,
+    pub token_1_amount: u128,
+}
+
+#[derive(PartialEq, Eq)]
+pub enum RoundDirection {
+    Floor,
+    Ceiling,
+}
+
+pub fn lp_tokens_to_trading_tokens(
+    lp_token_amount: u128,
+    lp_token_supply: u128,
+    swap_token_0_amount: u128,
+    swap_token_1_amount: u128,
+    round_direction: RoundDirection,
+) -> Option<TradingTokenResult> {
+    if lp_token_supply == 0 {
+        return None;
+    }
+    let mut token_0_amount = lp_token_amount
+        .checked_mul(swap_token_0_amount)?
+        .checked_div(lp_token_supply)?;
+    let mut token_1_amount = lp_token_amount
+        .checked_mul(swap_token_1_amount)?
+        .checked_div(lp_token_supply)?;
+
+    if round_direction == RoundDirection::Ceiling {
+        let token_0_remainder = lp_token_amount
+            .checked_mul(swap_token_0_amount)?
+            .checked_rem(lp_token_supply)?;
+        if token_0_remainder > 0 && token_0_amount > 0 {
+            token_0_amount = token_0_amount.checked_add(1)?;
+        }
+        let token_1_remainder = lp_token_amount
+            .checked_mul(swap_token_1_amount)?
+            .checked_rem(lp_token_supply)?;
+        if token_1_remainder > 0 && token_1_amount > 0 {
+            token_1_amount = token_1_amount.checked_add(1)?;
+        }
+    }
+    Some(TradingTokenResult {
+        token_0_amount,
+        token_1_amount,
+    })
+}
+
+fn ceil_div(numerator: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    numerator
+        .checked_add(denominator.checked_sub(1)?)?
+        .checked_div(denominator)
+}
+
+/// Trade fee charged on `amount`, rounded up as the program does.
+pub fn trading_fee(amount: u128, trade_fee_rate: u64) -> Option<u128> {
+    ceil_div(
+        amount.checked_mul(trade_fee_rate as u128)?,
+        FEE_RATE_DENOMINATOR_VALUE as u128,
+    )
+}
+
+/// Share of `trade_fee` owed to the protocol, rounded down.
+pub fn protocol_fee(trade_fee: u128, protocol_fee_rate: u64) -> Option<u128> {
+    trade_fee
+        .checked_mul(protocol_fee_rate as u128)?
+        .checked_div(FEE_RATE_DENOMINATOR_VALUE as u128)
+}
+
+/// Share of `trade_fee` owed to the fund, rounded down.
+pub fn fund_fee(trade_fee: u128, fund_fee_rate: u64) -> Option<u128> {
+    trade_fee
+        .checked_mul(fund_fee_rate as u128)?
+        .checked_div(FEE_RATE_DENOMINATOR_VALUE as u128)
+}
+
+#[derive(Debug)]
+pub struct SwapResult {
+    pub source_amount_swapped: u128,
+    pub destination_amount_swapped: u128,
+    pub trade_fee: u128,
+}
+
+pub fn swap_base_input(
+    source_amount: u128,
+    swap_source_amount: u128,
+    swap_destination_amount: u128,
+    trade_fee_rate: u64,
+) -> Option<SwapResult> {
+    let trade_fee = trading_fee(source_amount, trade_fee_rate)?;
+    let source_amount_less_fees = source_amount.checked_sub(trade_fee)?;
+
+    let destination_amount_swapped = source_amount_less_fees
+        .checked_mul(swap_destination_amount)?
+        .checked_div(swap_source_amount.checked_add(source_amount_less_fees)?)?;
+
+    Some(SwapResult {
+        source_amount_swapped: source_amount,
+        destination_amount_swapped,
+        trade_fee,
+    })
+}
+
+/// Amount that, after the trade fee is taken out, leaves `post_fee_amount`.
+pub fn pre_fee_amount(post_fee_amount: u128, trade_fee_rate: u64) -> Option<u128> {
+    if trade_fee_rate == 0 {
+        return Some(post_fee_amount);
+    }
+    ceil_div(
+        post_fee_amount.checked_mul(FEE_RATE_DENOMINATOR_VALUE as u128)?,
+        (FEE_RATE_DENOMINATOR_VALUE as u128).checked_sub(trade_fee_rate as u128)?,
+    )
+}
+
+/// Returns `None` when `destination_amount` would drain the destination
+/// reserve, since no input amount can buy it.
+pub fn swap_base_output(
+    destination_amount: u128,
+    swap_source_amount: u128,
+    swap_destination_amount: u128,
+    trade_fee_rate: u64,
+) -> Option<SwapResult> {
+    if destination_amount >= swap_destination_amount {
+        return None;
+    }
+    let source_amount_swapped = ceil_div(
+        swap_source_amount.checked_mul(destination_amount)?,
+        swap_destination_amount.checked_sub(destination_amount)?,
+    )?;
+
+    let source_amount = pre_fee_amount(source_amount_swapped, trade_fee_rate)?;
+    let trade_fee = trading_fee(source_amount, trade_fee_rate)?;
+
+    Some(SwapResult {
+        source_amount_swapped: source_amount,
+        destination_amount_swapped: destination_amount,
+        trade_fee,
+    })
+}
+
+/// Largest LP amount whose deposit needs at most `token_amount` from a side
+/// holding `swap_token_amount`. Rounds down, so the ceiling-rounded deposit
+/// of the result never exceeds `token_amount`.
+pub fn trading_tokens_to_lp_tokens(
+    token_amount: u128,
+    lp_token_supply: u128,
+    swap_token_amount: u128,
+) -> Option<u128> {
+    if swap_token_amount == 0 {
+        return None;
+    }
+    token_amount
+        .checked_mul(lp_token_supply)?
+        .checked_div(swap_token_amount)
+}
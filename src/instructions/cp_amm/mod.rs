@@ -1,5 +1,12 @@
 pub mod cp_amm_atomic_deposit_withdraw;
 pub mod cp_amm_atomic_withdraw_deposit;
+pub mod cp_amm_config;
 pub mod cp_amm_create_pool;
 pub mod cp_amm_deposit_liquidity;
+pub mod cp_amm_pool;
+pub mod cp_amm_quote;
+pub mod cp_amm_swap_base_input;
+pub mod cp_amm_swap_base_output;
+pub mod cp_amm_transfer_fee;
 pub mod cp_amm_withdraw_liquidity;
+pub mod curve_calculator;
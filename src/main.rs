@@ -1,6 +1,12 @@
+mod cli;
 mod instructions;
 mod utils;
 
+use clap::Parser;
+
 fn main() {
-    println!("Raydium API");
+    if let Err(e) = cli::run(cli::Cli::parse()) {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
 }
@@ -1 +1,5 @@
-pub mod program_utils;
\ No newline at end of file
+pub mod program_utils;
+pub mod signer_utils;
+pub mod slippage;
+pub mod token_utils;
+pub mod transaction_utils;
\ No newline at end of file
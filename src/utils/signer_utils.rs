@@ -0,0 +1,65 @@
+use anyhow::{Result, format_err};
+use solana_sdk::signature::{Keypair, Signer, read_keypair_file};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Where to load a transaction signer from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerSource {
+    /// Solana CLI keypair file: a JSON array of the 64 secret key bytes.
+    File(PathBuf),
+    /// Environment variable holding a base58-encoded 64-byte secret key.
+    Env(String),
+    /// The Solana CLI default, `~/.config/solana/id.json`.
+    Default,
+}
+
+pub fn default_keypair_path() -> Result<PathBuf> {
+    let home = env::var_os("HOME").ok_or_else(|| format_err!("HOME is not set"))?;
+    Ok(PathBuf::from(home).join(".config/solana/id.json"))
+}
+
+fn expand_home(path: &Path) -> Result<PathBuf> {
+    match path.strip_prefix("~") {
+        Ok(rest) => {
+            let home = env::var_os("HOME").ok_or_else(|| format_err!("HOME is not set"))?;
+            Ok(PathBuf::from(home).join(rest))
+        }
+        Err(_) => Ok(path.to_path_buf()),
+    }
+}
+
+pub fn keypair_from_file(path: &Path) -> Result<Keypair> {
+    let path = expand_home(path)?;
+    read_keypair_file(&path)
+        .map_err(|e| format_err!("Failed to read keypair file {}: {}", path.display(), e))
+}
+
+pub fn keypair_from_base58(secret_key: &str) -> Result<Keypair> {
+    let bytes = bs58::decode(secret_key.trim())
+        .into_vec()
+        .map_err(|e| format_err!("Secret key is not valid base58: {}", e))?;
+    if bytes.len() != 64 {
+        return Err(format_err!(
+            "Secret key must be 64 bytes long, but got {}",
+            bytes.len()
+        ));
+    }
+    Keypair::try_from(bytes.as_slice())
+        .map_err(|e| format_err!("Failed to create keypair from secret key bytes: {}", e))
+}
+
+pub fn keypair_from_env(var: &str) -> Result<Keypair> {
+    let secret_key = env::var(var)
+        .map_err(|e| format_err!("Failed to read environment variable {}: {}", var, e))?;
+    keypair_from_base58(&secret_key)
+}
+
+pub fn load_signer(source: &SignerSource) -> Result<Box<dyn Signer>> {
+    let keypair = match source {
+        SignerSource::File(path) => keypair_from_file(path)?,
+        SignerSource::Env(var) => keypair_from_env(var)?,
+        SignerSource::Default => keypair_from_file(&default_keypair_path()?)?,
+    };
+    Ok(Box::new(keypair))
+}
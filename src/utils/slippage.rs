@@ -0,0 +1,76 @@
+use anyhow::{Result, format_err};
+
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// How far the on-chain bound may stray from a quoted amount.
+///
+/// Tolerances are always rounded down, so a bound never allows more than the
+/// stated slippage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slippage {
+    /// Fraction of the quoted amount, in basis points.
+    Bps(u64),
+    /// Fixed number of base units either side of the quoted amount.
+    Absolute(u64),
+}
+
+impl Default for Slippage {
+    fn default() -> Self {
+        Slippage::Bps(100)
+    }
+}
+
+impl Slippage {
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            Slippage::Bps(bps) if bps > BPS_DENOMINATOR => Err(format_err!(
+                "Slippage of {} bps exceeds {} bps",
+                bps,
+                BPS_DENOMINATOR
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn tolerance(&self, amount: u64) -> Result<u64> {
+        self.validate()?;
+        Ok(match *self {
+            Slippage::Bps(bps) => (amount as u128 * bps as u128 / BPS_DENOMINATOR as u128) as u64,
+            Slippage::Absolute(tolerance) => tolerance,
+        })
+    }
+
+    /// Upper bound to pass as a `maximum_*` / `max_amount_in` argument.
+    pub fn max_amount(&self, amount: u64) -> Result<u64> {
+        amount
+            .checked_add(self.tolerance(amount)?)
+            .ok_or_else(|| format_err!("Slippage bound for {} overflows", amount))
+    }
+
+    /// Upper bound for a deposit quoted against `cap` less slippage (see
+    /// [`Slippage::quote_within`]). Sizing that way lets the deposit still go
+    /// through if the price moves before it lands, while the bound itself is
+    /// clamped so it never breaches the user's cap.
+    pub fn max_amount_capped(&self, amount: u64, cap: Option<u64>) -> Result<u64> {
+        let amount = self.max_amount(amount)?;
+        Ok(cap.map_or(amount, |cap| amount.min(cap)))
+    }
+
+    /// Lower bound to pass as a `minimum_*` argument. Saturates at zero when
+    /// an absolute tolerance exceeds the amount.
+    pub fn min_amount(&self, amount: u64) -> Result<u64> {
+        Ok(amount.saturating_sub(self.tolerance(amount)?))
+    }
+
+    /// Inverse of [`Slippage::max_amount`]: the largest quote whose upper
+    /// bound still fits within `maximum`.
+    pub fn quote_within(&self, maximum: u64) -> Result<u64> {
+        self.validate()?;
+        Ok(match *self {
+            Slippage::Bps(bps) => {
+                (maximum as u128 * BPS_DENOMINATOR as u128 / (BPS_DENOMINATOR + bps) as u128) as u64
+            }
+            Slippage::Absolute(tolerance) => maximum.saturating_sub(tolerance),
+        })
+    }
+}
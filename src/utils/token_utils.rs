@@ -0,0 +1,35 @@
+use anyhow::{Result, format_err};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Mint;
+
+/// The owning token program and decimals of a mint.
+#[derive(Debug, Clone, Copy)]
+pub struct MintInfo {
+    pub token_program: Pubkey,
+    pub decimals: u8,
+}
+
+/// Reads `mint`, which must be owned by either SPL Token or Token-2022.
+pub fn fetch_mint_info(rpc_client: &RpcClient, mint: &Pubkey) -> Result<MintInfo> {
+    let mint_account = rpc_client.get_account(mint)?;
+    if mint_account.owner != spl_token::id() && mint_account.owner != spl_token_2022::id() {
+        return Err(format_err!(
+            "Mint {} is owned by {}, which is not a token program",
+            mint,
+            mint_account.owner
+        ));
+    }
+    let state = StateWithExtensions::<Mint>::unpack(&mint_account.data)?;
+    Ok(MintInfo {
+        token_program: mint_account.owner,
+        decimals: state.base.decimals,
+    })
+}
+
+/// Returns the token program that owns `mint`, which is either SPL Token or
+/// Token-2022.
+pub fn fetch_mint_token_program(rpc_client: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    Ok(fetch_mint_info(rpc_client, mint)?.token_program)
+}
@@ -0,0 +1,201 @@
+use anyhow::{Result, format_err};
+use solana_address_lookup_table_interface::{
+    instruction as lookup_table_instruction, state::AddressLookupTable,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::{AddressLookupTableAccount, Message, VersionedMessage, v0},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::Transaction,
+};
+use std::thread;
+use std::time::Duration;
+
+/// Most addresses one `extend_lookup_table` instruction can carry and still
+/// fit in a transaction.
+const MAX_LOOKUP_TABLE_EXTEND_ADDRESSES: usize = 20;
+
+const SLOT_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Compiles `instructions` into a legacy message, or a v0 message resolving
+/// accounts through `lookup_tables` when any are given.
+pub fn compile_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    lookup_tables: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedMessage> {
+    if lookup_tables.is_empty() {
+        return Ok(VersionedMessage::Legacy(Message::new_with_blockhash(
+            instructions,
+            Some(payer),
+            &recent_blockhash,
+        )));
+    }
+    Ok(VersionedMessage::V0(v0::Message::try_compile(
+        payer,
+        instructions,
+        lookup_tables,
+        recent_blockhash,
+    )?))
+}
+
+/// Size of the transaction carrying `message` once signed.
+pub fn signed_transaction_size(message: &VersionedMessage) -> usize {
+    let signatures = message.header().num_required_signatures as usize;
+    // The signature count is a compact-u16, one byte below 128.
+    1 + signatures * 64 + message.serialize().len()
+}
+
+pub fn fits_in_packet(message: &VersionedMessage) -> bool {
+    signed_transaction_size(message) <= PACKET_DATA_SIZE
+}
+
+/// Splits `instructions`, in order, into as few legacy transactions paid by
+/// `payer` as fit in a packet each.
+pub fn pack_instructions(
+    payer: &Pubkey,
+    instructions: Vec<Instruction>,
+) -> Result<Vec<Vec<Instruction>>> {
+    let fits = |instructions: &[Instruction]| -> Result<bool> {
+        Ok(fits_in_packet(&compile_message(
+            payer,
+            instructions,
+            &[],
+            Hash::default(),
+        )?))
+    };
+    let mut batches = Vec::new();
+    let mut batch: Vec<Instruction> = Vec::new();
+    for instruction in instructions {
+        batch.push(instruction);
+        if fits(&batch)? {
+            continue;
+        }
+        let instruction = batch.pop().expect("batch has the instruction just pushed");
+        if !batch.is_empty() {
+            batches.push(std::mem::take(&mut batch));
+        }
+        batch.push(instruction);
+        if !fits(&batch)? {
+            return Err(format_err!(
+                "An instruction for program {} does not fit in a transaction",
+                batch[0].program_id
+            ));
+        }
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+pub fn fetch_lookup_table(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+) -> Result<AddressLookupTableAccount> {
+    let account = rpc_client.get_account(address)?;
+    let table = AddressLookupTable::deserialize(&account.data)
+        .map_err(|err| format_err!("Account {} is not a lookup table: {}", address, err))?;
+    Ok(AddressLookupTableAccount {
+        key: *address,
+        addresses: table.addresses.to_vec(),
+    })
+}
+
+/// Creates a lookup table owned by `authority` holding `addresses` and waits
+/// until it can be used. Returns the table and the signatures of the
+/// transactions that built it.
+pub fn create_lookup_table(
+    rpc_client: &RpcClient,
+    authority: &dyn Signer,
+    addresses: &[Pubkey],
+) -> Result<(AddressLookupTableAccount, Vec<Signature>)> {
+    if addresses.is_empty() {
+        return Err(format_err!("A lookup table needs at least one address"));
+    }
+    let recent_slot = rpc_client.get_slot()?;
+    let (create_instruction, table) = lookup_table_instruction::create_lookup_table(
+        authority.pubkey(),
+        authority.pubkey(),
+        recent_slot,
+    );
+    println!("Lookup Table: {}", table);
+
+    let mut signatures = Vec::new();
+    let mut instructions = vec![create_instruction];
+    for chunk in addresses.chunks(MAX_LOOKUP_TABLE_EXTEND_ADDRESSES) {
+        instructions.push(lookup_table_instruction::extend_lookup_table(
+            table,
+            authority.pubkey(),
+            Some(authority.pubkey()),
+            chunk.to_vec(),
+        ));
+        let latest_blockhash = rpc_client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&authority.pubkey()),
+            &[authority],
+            latest_blockhash,
+        );
+        println!("Sending lookup table transaction...");
+        signatures.push(rpc_client.send_and_confirm_transaction_with_spinner(&transaction)?);
+        instructions.clear();
+    }
+
+    // Addresses become usable in the slot after the one that added them.
+    let extended_slot = rpc_client.get_slot()?;
+    while rpc_client.get_slot()? <= extended_slot {
+        thread::sleep(SLOT_POLL_INTERVAL);
+    }
+
+    Ok((
+        AddressLookupTableAccount {
+            key: table,
+            addresses: addresses.to_vec(),
+        },
+        signatures,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    fn instruction(program_id: Pubkey, accounts: usize) -> Instruction {
+        Instruction::new_with_bytes(
+            program_id,
+            &[0; 16],
+            (0..accounts)
+                .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn packs_instructions_in_order_within_the_packet_limit() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let instructions: Vec<_> = (0..5).map(|_| instruction(program_id, 12)).collect();
+        let batches = pack_instructions(&payer, instructions.clone()).unwrap();
+
+        assert!(batches.len() > 1);
+        for batch in &batches {
+            let message = compile_message(&payer, batch, &[], Hash::default()).unwrap();
+            assert!(fits_in_packet(&message));
+        }
+        assert_eq!(batches.concat(), instructions);
+    }
+
+    #[test]
+    fn rejects_an_instruction_too_large_for_any_transaction() {
+        let payer = Pubkey::new_unique();
+        let instructions = vec![instruction(Pubkey::new_unique(), 40)];
+        assert!(pack_instructions(&payer, instructions).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+#!/usr/bin/env python3
+"""Regenerates the synthetic CLMM swap fixtures in this directory.
+
+The swap loop below is a reference implementation written from the Raydium
+CLMM program's `swap_internal` and `compute_swap_step`, using Python's
+unbounded integers so intermediate products never overflow. It shares no
+code with `swap_simulator.rs`, so the fixtures check the simulator against
+an independent reading of the program.
+
+    python3 tests/fixtures/clmm_swap/generate.py
+
+rewrites every fixture; `git diff` should then show no changes.
+"""
+
+import base64
+import json
+import struct
+from pathlib import Path
+
+Q64 = 1 << 64
+MIN_TICK, MAX_TICK = -443636, 443636
+MIN_SQRT_PRICE_X64 = 4295048016
+MAX_SQRT_PRICE_X64 = 79226673521066979257578248091
+FEE_RATE_DENOMINATOR = 1_000_000
+TICK_ARRAY_SIZE = 60
+
+# sqrt(1.0001^-(2^i)) in Q64.64, as in the program's tick_math.
+RATIOS = [
+    0xFFFCB933BD6FB800, 0xFFF97272373D4000, 0xFFF2E50F5F657000,
+    0xFFE5CACA7E10F000, 0xFFCB9843D60F7000, 0xFF973B41FA98E800,
+    0xFF2EA16466C9B000, 0xFE5DEE046A9A3800, 0xFCBE86C7900BB000,
+    0xF987A7253AC65800, 0xF3392B0822BB6000, 0xE7159475A2CAF000,
+    0xD097F3BDFD2F2000, 0xA9F746462D9F8000, 0x70D869A156F31C00,
+    0x31BE135F97ED3200, 0x9AA508B5B85A500, 0x5D6AF8DEDC582C,
+    0x2216E584F5FA,
+]
+
+
+def ceil_div(a, b):
+    return -(-a // b)
+
+
+def sqrt_price_at_tick(tick):
+    abs_tick = abs(tick)
+    ratio = RATIOS[0] if abs_tick & 1 else Q64
+    for i in range(1, len(RATIOS)):
+        if abs_tick & (1 << i):
+            ratio = (ratio * RATIOS[i]) >> 64
+    if tick > 0:
+        ratio = ((1 << 128) - 1) // ratio
+    return ratio
+
+
+def tick_at_sqrt_price(sqrt_price_x64):
+    """The greatest tick whose sqrt price is at most `sqrt_price_x64`."""
+    low, high = MIN_TICK, MAX_TICK
+    while low < high:
+        mid = (low + high + 1) // 2
+        if sqrt_price_at_tick(mid) <= sqrt_price_x64:
+            low = mid
+        else:
+            high = mid - 1
+    return low
+
+
+def amount_0_delta(a, b, liquidity, round_up):
+    a, b = min(a, b), max(a, b)
+    numerator = (liquidity << 64) * (b - a)
+    if round_up:
+        return ceil_div(ceil_div(numerator, b), a)
+    return numerator // b // a
+
+
+def amount_1_delta(a, b, liquidity, round_up):
+    a, b = min(a, b), max(a, b)
+    if round_up:
+        return ceil_div(liquidity * (b - a), Q64)
+    return liquidity * (b - a) // Q64
+
+
+def next_sqrt_price_from_input(sqrt_price, liquidity, amount, zero_for_one):
+    if zero_for_one:
+        if amount == 0:
+            return sqrt_price
+        numerator = liquidity << 64
+        return ceil_div(numerator * sqrt_price, numerator + amount * sqrt_price)
+    return sqrt_price + (amount << 64) // liquidity
+
+
+def next_sqrt_price_from_output(sqrt_price, liquidity, amount, zero_for_one):
+    if zero_for_one:
+        return sqrt_price - ceil_div(amount << 64, liquidity)
+    if amount == 0:
+        return sqrt_price
+    numerator = liquidity << 64
+    return ceil_div(numerator * sqrt_price, numerator - amount * sqrt_price)
+
+
+def swap_step(sqrt_current, sqrt_target, liquidity, remaining, fee_rate, is_base_input, zero_for_one):
+    """One step of the program's `compute_swap_step`."""
+    if is_base_input:
+        usable = remaining * (FEE_RATE_DENOMINATOR - fee_rate) // FEE_RATE_DENOMINATOR
+        if zero_for_one:
+            needed = amount_0_delta(sqrt_target, sqrt_current, liquidity, True)
+        else:
+            needed = amount_1_delta(sqrt_current, sqrt_target, liquidity, True)
+        if usable >= needed:
+            sqrt_next = sqrt_target
+        else:
+            sqrt_next = next_sqrt_price_from_input(sqrt_current, liquidity, usable, zero_for_one)
+    else:
+        if zero_for_one:
+            needed = amount_1_delta(sqrt_target, sqrt_current, liquidity, False)
+        else:
+            needed = amount_0_delta(sqrt_current, sqrt_target, liquidity, False)
+        if remaining >= needed:
+            sqrt_next = sqrt_target
+        else:
+            sqrt_next = next_sqrt_price_from_output(sqrt_current, liquidity, remaining, zero_for_one)
+
+    reached_target = sqrt_next == sqrt_target
+    if zero_for_one:
+        amount_in = needed if reached_target and is_base_input else amount_0_delta(sqrt_next, sqrt_current, liquidity, True)
+        amount_out = needed if reached_target and not is_base_input else amount_1_delta(sqrt_next, sqrt_current, liquidity, False)
+    else:
+        amount_in = needed if reached_target and is_base_input else amount_1_delta(sqrt_current, sqrt_next, liquidity, True)
+        amount_out = needed if reached_target and not is_base_input else amount_0_delta(sqrt_current, sqrt_next, liquidity, False)
+    if not is_base_input and amount_out > remaining:
+        amount_out = remaining
+
+    if is_base_input and not reached_target:
+        fee_amount = remaining - amount_in
+    else:
+        fee_amount = ceil_div(amount_in * fee_rate, FEE_RATE_DENOMINATOR - fee_rate)
+    return sqrt_next, amount_in, amount_out, fee_amount
+
+
+def simulate(sqrt_price, tick, liquidity, liquidity_net, fee_rate, zero_for_one, amount, sqrt_price_limit, is_base_input):
+    """The program's swap loop over the initialised ticks in `liquidity_net`."""
+    remaining = amount
+    amount_in = amount_out = fee_amount = 0
+    while remaining and sqrt_price != sqrt_price_limit:
+        if zero_for_one:
+            next_tick = max(t for t in liquidity_net if t <= tick)
+        else:
+            next_tick = min(t for t in liquidity_net if t > tick)
+        sqrt_next_tick = sqrt_price_at_tick(next_tick)
+        if zero_for_one:
+            sqrt_target = max(sqrt_next_tick, sqrt_price_limit)
+        else:
+            sqrt_target = min(sqrt_next_tick, sqrt_price_limit)
+
+        sqrt_start = sqrt_price
+        sqrt_price, step_in, step_out, step_fee = swap_step(
+            sqrt_price, sqrt_target, liquidity, remaining, fee_rate, is_base_input, zero_for_one
+        )
+        remaining -= step_in + step_fee if is_base_input else step_out
+        amount_in += step_in + step_fee
+        amount_out += step_out
+        fee_amount += step_fee
+
+        if sqrt_price == sqrt_next_tick:
+            net = liquidity_net[next_tick]
+            liquidity += -net if zero_for_one else net
+            tick = next_tick - 1 if zero_for_one else next_tick
+        elif sqrt_price != sqrt_start:
+            tick = tick_at_sqrt_price(sqrt_price)
+    return {
+        "amount_in": amount_in,
+        "amount_out": amount_out,
+        "fee_amount": fee_amount,
+        "sqrt_price_x64": sqrt_price,
+        "tick_current": tick,
+    }
+
+
+# Account layouts, zeroed except for the fields the swap reads.
+
+def pool_state_data(sqrt_price, tick, liquidity, tick_spacing):
+    data = bytearray(1544)
+    data[0:8] = bytes([247, 237, 227, 245, 215, 195, 222, 70])
+    struct.pack_into("<H", data, 235, tick_spacing)
+    data[237:253] = liquidity.to_bytes(16, "little")
+    data[253:269] = sqrt_price.to_bytes(16, "little")
+    struct.pack_into("<i", data, 269, tick)
+    return bytes(data)
+
+
+def amm_config_data(trade_fee_rate, tick_spacing):
+    data = bytearray(117)
+    data[0:8] = bytes([218, 244, 33, 104, 203, 203, 43, 111])
+    # protocol_fee_rate, trade_fee_rate, tick_spacing, fund_fee_rate
+    struct.pack_into("<IIHI", data, 8 + 1 + 2 + 32, 120000, trade_fee_rate, tick_spacing, 40000)
+    return bytes(data)
+
+
+def tick_array_data(start_tick_index, tick_spacing, liquidity_net):
+    data = bytearray(10240)
+    data[0:8] = bytes([192, 155, 85, 205, 49, 249, 129, 42])
+    struct.pack_into("<i", data, 40, start_tick_index)
+    for tick, net in liquidity_net.items():
+        if start_tick_index <= tick < start_tick_index + TICK_ARRAY_SIZE * tick_spacing:
+            offset = 44 + (tick - start_tick_index) // tick_spacing * 168
+            struct.pack_into("<i", data, offset, tick)
+            data[offset + 4:offset + 20] = (net % (1 << 128)).to_bytes(16, "little")
+            data[offset + 20:offset + 36] = abs(net).to_bytes(16, "little")
+    return bytes(data)
+
+
+def encode(data):
+    return base64.b64encode(data).decode()
+
+
+TICK_SPACING = 10
+TRADE_FEE_RATE = 2500
+LIQUIDITY_NET = {-600: 10**12, -200: 5 * 10**11, 100: -5 * 10**11, 600: -10**12}
+TICK_CURRENT = 50
+SQRT_PRICE_X64 = sqrt_price_at_tick(TICK_CURRENT) + 12345
+LIQUIDITY = 15 * 10**11
+
+# name, zero_for_one, amount_specified, is_base_input, tick array starts, limit
+CASES = [
+    ("zero_for_one_exact_in", True, 3 * 10**10, True, [0, -600], None),
+    ("one_for_zero_exact_in", False, 10**10, True, [0, 600], None),
+    ("zero_for_one_exact_out", True, 2 * 10**10, False, [0, -600], None),
+    ("one_for_zero_exact_out", False, 10**10, False, [0, 600], None),
+    ("zero_for_one_price_limit", True, 10**12, True, [0, -600], sqrt_price_at_tick(-300) + 777),
+]
+
+
+def main():
+    directory = Path(__file__).resolve().parent
+    for name, zero_for_one, amount, is_base_input, starts, limit in CASES:
+        if limit is None:
+            limit = MIN_SQRT_PRICE_X64 + 1 if zero_for_one else MAX_SQRT_PRICE_X64 - 1
+        expected = simulate(
+            SQRT_PRICE_X64, TICK_CURRENT, LIQUIDITY, LIQUIDITY_NET, TRADE_FEE_RATE,
+            zero_for_one, amount, limit, is_base_input,
+        )
+        fixture = {
+            "pool_state": encode(pool_state_data(SQRT_PRICE_X64, TICK_CURRENT, LIQUIDITY, TICK_SPACING)),
+            "amm_config": encode(amm_config_data(TRADE_FEE_RATE, TICK_SPACING)),
+            "tick_arrays": [encode(tick_array_data(start, TICK_SPACING, LIQUIDITY_NET)) for start in starts],
+            "zero_for_one": zero_for_one,
+            "amount_specified": amount,
+            "is_base_input": is_base_input,
+            "sqrt_price_limit_x64": limit,
+            "expected": expected,
+        }
+        (directory / f"{name}.json").write_text(json.dumps(fixture, indent=2))
+        print(name, expected)
+
+
+if __name__ == "__main__":
+    main()